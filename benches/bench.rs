@@ -5,7 +5,10 @@ use color_eyre::eyre::Result;
 use criterion::{Criterion, black_box, criterion_group, criterion_main};
 
 use dummyui::DummyUI;
-use ltrait::{Launcher, filter::ClosureFilter, sorter::ClosureSorter, source::from_iter};
+use ltrait::{
+    Launcher, filter::ClosureFilter, scorer::ClosureScorer, sorter::ClosureSorter,
+    source::from_iter,
+};
 
 use std::convert::identity;
 
@@ -68,6 +71,32 @@ async fn simple_sorter_a() -> Result<()> {
     Ok(())
 }
 
+async fn simple_scorer_a() -> Result<()> {
+    let launcher = Launcher::default()
+        .add_source(from_iter(0..black_box(500_000)), identity)
+        .batch_size(10_000)
+        .add_scorer(
+            ClosureScorer::new(|&x: &i32, _| black_box(x as f64)),
+            1.0,
+            |&c: &i32| c,
+        )
+        .add_scorer(
+            ClosureScorer::new(|&x: &i32, _| black_box((x % 7) as f64)),
+            2.0,
+            |&c: &i32| c,
+        )
+        .add_scorer(
+            ClosureScorer::new(|_: &i32, _| black_box(0.0)),
+            3.0,
+            |&c: &i32| c,
+        )
+        .set_ui(DummyUI::new(|_: &()| {}), |_| ());
+
+    launcher.run().await?;
+
+    Ok(())
+}
+
 async fn three_sources_a() -> Result<()> {
     let launcher = Launcher::default()
         .add_source(from_iter(0..black_box(150_000)), identity)
@@ -81,6 +110,24 @@ async fn three_sources_a() -> Result<()> {
     Ok(())
 }
 
+async fn limited_sorter_a() -> Result<()> {
+    let launcher = Launcher::default()
+        .add_source(from_iter(0..black_box(500_000)), identity)
+        .batch_size(10_000)
+        .limit(50)
+        .add_sorter(
+            ClosureSorter::new(|lhs: &i32, rhs: &i32, _| black_box(rhs.cmp(lhs))),
+            |&c: &i32| c,
+        )
+        .set_ui(DummyUI::new(|_: &()| {}), |_| ());
+
+    launcher.run().await?;
+
+    Ok(())
+}
+
+// ベンチは関数をクロージャで包む書き方のままにする
+#[allow(clippy::redundant_closure)]
 fn simple_source(c: &mut Criterion) {
     c.bench_function("500,000 Items, batch_size = 10,000", |b| {
         b.to_async(Runtime::new().unwrap())
@@ -88,6 +135,7 @@ fn simple_source(c: &mut Criterion) {
     });
 }
 
+#[allow(clippy::redundant_closure)]
 fn simple_filter(c: &mut Criterion) {
     c.bench_function("500,000 Items, batch_size = 10,000, 3 filters", |b| {
         b.to_async(Runtime::new().unwrap())
@@ -95,6 +143,7 @@ fn simple_filter(c: &mut Criterion) {
     });
 }
 
+#[allow(clippy::redundant_closure)]
 fn simple_sorter(c: &mut Criterion) {
     c.bench_function("500,000 Items, batch_size = 10,000, 3 sorters", |b| {
         b.to_async(Runtime::new().unwrap())
@@ -102,6 +151,15 @@ fn simple_sorter(c: &mut Criterion) {
     });
 }
 
+#[allow(clippy::redundant_closure)]
+fn simple_scorer(c: &mut Criterion) {
+    c.bench_function("500,000 Items, batch_size = 10,000, 3 scorers", |b| {
+        b.to_async(Runtime::new().unwrap())
+            .iter(|| simple_scorer_a());
+    });
+}
+
+#[allow(clippy::redundant_closure)]
 fn three_sources(c: &mut Criterion) {
     c.bench_function("450,000 Items, batch_size = 10,000, 3 sources", |b| {
         b.to_async(Runtime::new().unwrap())
@@ -109,11 +167,24 @@ fn three_sources(c: &mut Criterion) {
     });
 }
 
+#[allow(clippy::redundant_closure)]
+fn limited_sorter(c: &mut Criterion) {
+    c.bench_function(
+        "500,000 Items, batch_size = 10,000, limit = 50, 1 sorter",
+        |b| {
+            b.to_async(Runtime::new().unwrap())
+                .iter(|| limited_sorter_a());
+        },
+    );
+}
+
 criterion_group!(
     benches,
     simple_source,
     simple_filter,
     simple_sorter,
+    simple_scorer,
     three_sources,
+    limited_sorter,
 );
 criterion_main!(benches);
@@ -0,0 +1,282 @@
+//! Derive macros for [ltrait](https://docs.rs/ltrait/). Use them through `ltrait::Cushion` with the `derive` feature.
+
+use std::collections::BTreeMap;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Ident, LitStr, Variant, parse_macro_input};
+
+/// Generates the boilerplate of a `Cushion` enum.
+///
+/// For each variant with fields, it generates
+///
+/// * a constructor named in snake_case, taking the field (or a tuple of the fields),
+///   to be used as the transformer of `add_source`
+/// * `From<field type>` if the variant has exactly one field and no other variant has a single field
+///   of the same type, unless `#[cushion(skip_from)]` is given
+///
+/// With `#[cushion(display = "...")]` on the variants, it also generates `display_string(&self) -> String`,
+/// and with `#[cushion(haystack = "...")]`, `haystack(&self) -> String` for filters and scorers
+/// (falling back to `display` on the variants without `haystack`).
+/// The strings are `format!` strings where named fields are used by name and tuple fields as `{0}`, `{1}`, ....
+///
+/// ```
+/// use ltrait_derive::Cushion;
+///
+/// #[derive(Cushion)]
+/// enum Item {
+///     #[cushion(display = "{0}")]
+///     Num(u32),
+///     #[cushion(display = "{name}", haystack = "{name} {path}")]
+///     App { name: String, path: String },
+///     #[cushion(display = "quit", skip_from)]
+///     Quit,
+/// }
+///
+/// assert_eq!(Item::num(1).display_string(), "1");
+/// assert_eq!(Item::from(1).haystack(), "1");
+///
+/// let app = Item::app(("Firefox".into(), "/usr/bin/firefox".into()));
+/// assert_eq!(app.display_string(), "Firefox");
+/// assert_eq!(app.haystack(), "Firefox /usr/bin/firefox");
+/// ```
+#[proc_macro_derive(Cushion, attributes(cushion))]
+pub fn derive_cushion(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct Attrs {
+    display: Option<LitStr>,
+    haystack: Option<LitStr>,
+    skip_from: bool,
+}
+
+impl Attrs {
+    fn parse(variant: &Variant) -> syn::Result<Self> {
+        let mut attrs = Self::default();
+
+        for attr in variant
+            .attrs
+            .iter()
+            .filter(|a| a.path().is_ident("cushion"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("display") {
+                    attrs.display = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("haystack") {
+                    attrs.haystack = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("skip_from") {
+                    attrs.skip_from = true;
+                } else {
+                    return Err(meta.error("expected `display`, `haystack` or `skip_from`"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(attrs)
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Cushion can only be derived for enums",
+        ));
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let variants = data
+        .variants
+        .iter()
+        .map(|v| Ok((v, Attrs::parse(v)?)))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    // 同じ型のFromが複数あると衝突するので、その型のFromは作らない
+    let mut from_types = BTreeMap::new();
+    for (variant, attrs) in &variants {
+        if let (1, false) = (variant.fields.len(), attrs.skip_from) {
+            let ty = &variant.fields.iter().next().unwrap().ty;
+            *from_types.entry(quote!(#ty).to_string()).or_insert(0) += 1;
+        }
+    }
+
+    let mut constructors = vec![];
+    let mut froms = vec![];
+    for (variant, attrs) in &variants {
+        let ident = &variant.ident;
+        let types: Vec<_> = variant.fields.iter().map(|f| &f.ty).collect();
+        if types.is_empty() {
+            continue;
+        }
+
+        let constructor = constructor_name(ident);
+        // 1つならそのまま、複数ならタプルで受け取る
+        let (ty, construct) = match &variant.fields {
+            Fields::Named(fields) if types.len() == 1 => {
+                let field = &fields.named[0].ident;
+                (quote!(#(#types)*), quote!(Self::#ident { #field: value }))
+            }
+            Fields::Named(fields) => {
+                let fields = fields.named.iter().map(|f| &f.ident);
+                let indices = (0..types.len()).map(syn::Index::from);
+                (
+                    quote!((#(#types),*)),
+                    quote!(Self::#ident { #(#fields: value.#indices),* }),
+                )
+            }
+            _ if types.len() == 1 => (quote!(#(#types)*), quote!(Self::#ident(value))),
+            _ => {
+                let indices = (0..types.len()).map(syn::Index::from);
+                (
+                    quote!((#(#types),*)),
+                    quote!(Self::#ident(#(value.#indices),*)),
+                )
+            }
+        };
+
+        let doc = format!("Creates [`{name}::{ident}`]");
+        constructors.push(quote! {
+            #[doc = #doc]
+            pub fn #constructor(value: #ty) -> Self {
+                #construct
+            }
+        });
+
+        if types.len() == 1 && !attrs.skip_from && from_types.get(&ty.to_string()) == Some(&1) {
+            froms.push(quote! {
+                impl #impl_generics ::core::convert::From<#ty> for #name #ty_generics #where_clause {
+                    fn from(value: #ty) -> Self {
+                        Self::#constructor(value)
+                    }
+                }
+            });
+        }
+    }
+
+    let mut accessors = vec![];
+    if variants.iter().any(|(_, a)| a.display.is_some()) {
+        let arms = variants
+            .iter()
+            .map(|(v, a)| arm(v, a.display.as_ref(), "display"))
+            .collect::<syn::Result<Vec<_>>>()?;
+        accessors.push(quote! {
+            /// The string shown by the UI
+            #[allow(unused_variables)]
+            pub fn display_string(&self) -> ::std::string::String {
+                match self {
+                    #(#arms)*
+                }
+            }
+        });
+    }
+    if variants
+        .iter()
+        .any(|(_, a)| a.display.is_some() || a.haystack.is_some())
+    {
+        let arms = variants
+            .iter()
+            .map(|(v, a)| arm(v, a.haystack.as_ref().or(a.display.as_ref()), "haystack"))
+            .collect::<syn::Result<Vec<_>>>()?;
+        accessors.push(quote! {
+            /// The string matched against the input
+            #[allow(unused_variables)]
+            pub fn haystack(&self) -> ::std::string::String {
+                match self {
+                    #(#arms)*
+                }
+            }
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #(#constructors)*
+            #(#accessors)*
+        }
+
+        #(#froms)*
+    })
+}
+
+/// A match arm binding the fields of the variant and formatting `format`
+fn arm(variant: &Variant, format: Option<&LitStr>, attr: &str) -> syn::Result<TokenStream> {
+    let ident = &variant.ident;
+    let Some(format) = format else {
+        return Err(syn::Error::new_spanned(
+            ident,
+            format!("missing `#[cushion({attr} = \"...\")]`"),
+        ));
+    };
+
+    let pattern = match &variant.fields {
+        Fields::Named(fields) => {
+            let fields = fields.named.iter().map(|f| &f.ident);
+            quote!(Self::#ident { #(#fields),* })
+        }
+        Fields::Unnamed(fields) => {
+            let fields = (0..fields.unnamed.len()).map(|i| format_ident!("_{i}"));
+            quote!(Self::#ident(#(#fields),*))
+        }
+        Fields::Unit => quote!(Self::#ident),
+    };
+    let format = LitStr::new(&positional_to_named(&format.value()), format.span());
+
+    Ok(quote! {
+        #pattern => ::std::format!(#format),
+    })
+}
+
+/// Rewrites `{0}` to `{_0}` so that the bindings of tuple fields are captured by `format!`
+fn positional_to_named(format: &str) -> String {
+    let mut out = String::with_capacity(format.len());
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        out.push(c);
+        if c == '{' {
+            match chars.peek() {
+                Some('{') => out.push(chars.next().unwrap()),
+                Some(d) if d.is_ascii_digit() => out.push('_'),
+                _ => {}
+            }
+        }
+    }
+
+    out
+}
+
+/// snake_case of the variant name. A run of capitals is a word, e.g. `HTTPGet` to `http_get`.
+fn constructor_name(ident: &Ident) -> Ident {
+    let chars: Vec<_> = ident.to_string().chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let prev = i.checked_sub(1).map(|i| chars[i]);
+            let next = chars.get(i + 1);
+            // 小文字の後か、大文字の並びの最後(次が小文字)で区切る
+            let boundary = prev.is_some_and(|p| !p.is_uppercase() && p != '_')
+                || prev.is_some_and(char::is_uppercase) && next.is_some_and(|n| n.is_lowercase());
+            if boundary {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+
+    if syn::parse_str::<Ident>(&snake).is_ok() {
+        Ident::new(&snake, Span::call_site())
+    } else {
+        // `Type`や`Move`などのキーワード
+        Ident::new_raw(&snake, Span::call_site())
+    }
+}
@@ -1,10 +1,41 @@
 use color_eyre::Result;
 use std::marker::PhantomData;
 
+/// Whether a launcher in the loop mode stays open after an action.
+/// See [`Launcher::keep_open`](crate::launcher::Launcher::keep_open).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Flow {
+    #[default]
+    Continue,
+    Close,
+}
+
 pub trait Action: Send {
     type Context;
 
     fn act(&self, ctx: &Self::Context) -> Result<()>;
+
+    /// Called instead of [`Action::act`] when the launcher is kept open.
+    /// By default, it acts and keeps the launcher open.
+    fn act_in_loop(&self, ctx: &Self::Context) -> Result<Flow> {
+        self.act(ctx)?;
+        Ok(Flow::Continue)
+    }
+}
+
+impl<T> Action for Box<T>
+where
+    T: Action + ?Sized,
+{
+    type Context = T::Context;
+
+    fn act(&self, ctx: &Self::Context) -> Result<()> {
+        (**self).act(ctx)
+    }
+
+    fn act_in_loop(&self, ctx: &Self::Context) -> Result<Flow> {
+        (**self).act_in_loop(ctx)
+    }
 }
 
 pub struct ClosureAction<Context, F>(F, PhantomData<Context>)
@@ -34,6 +65,39 @@ where
     }
 }
 
+/// An action that decides whether a launcher in the loop mode stays open.
+/// When the launcher is not kept open, the [`Flow`] is ignored.
+pub struct ClosureFlowAction<Context, F>(F, PhantomData<Context>)
+where
+    F: Fn(&Context) -> Result<Flow> + Send,
+    Context: Sync;
+
+impl<Context, F> ClosureFlowAction<Context, F>
+where
+    F: Fn(&Context) -> Result<Flow> + Send,
+    Context: Sync,
+{
+    pub fn new(f: F) -> Self {
+        Self(f, PhantomData)
+    }
+}
+
+impl<Context, F> Action for ClosureFlowAction<Context, F>
+where
+    F: Fn(&Context) -> Result<Flow> + Send,
+    Context: Sync + Send,
+{
+    type Context = Context;
+
+    fn act(&self, ctx: &Self::Context) -> Result<()> {
+        (self.0)(ctx).map(|_| ())
+    }
+
+    fn act_in_loop(&self, ctx: &Self::Context) -> Result<Flow> {
+        (self.0)(ctx)
+    }
+}
+
 pub struct ActionWrapper<ActionContext, ActionT, F, Cushion>
 where
     F: Fn(&Cushion) -> ActionContext + Send,
@@ -58,6 +122,10 @@ where
     fn act(&self, ctx: &Self::Context) -> Result<()> {
         self.action.act(&(self.f)(ctx))
     }
+
+    fn act_in_loop(&self, ctx: &Self::Context) -> Result<Flow> {
+        self.action.act_in_loop(&(self.f)(ctx))
+    }
 }
 
 impl<ActionContext, ActionT, F, Cushion> ActionWrapper<ActionContext, ActionT, F, Cushion>
@@ -0,0 +1,205 @@
+//! Builds a [`Launcher`] from a TOML file.
+//!
+//! The extensions are looked up by `name` in a [`Registry`], and the other keys of the table (except `weight` of scorers) are
+//! passed to the factory as its options.
+//!
+//! ```toml
+//! batch_size = 500
+//! limit = 100
+//! filter_mode = "and" # or "or"
+//!
+//! [[source]]
+//! name = "numbers"
+//! count = 5000
+//!
+//! [[filter]]
+//! name = "even"
+//!
+//! [[scorer]]
+//! name = "length"
+//! weight = 2.0 # 1.0 if omitted
+//!
+//! [[action]]
+//! name = "print"
+//! ```
+//!
+//! The UI is not a part of the config, set it by [`Launcher::set_ui`] after building the launcher.
+
+use color_eyre::eyre::{Context as _, Result, eyre};
+use serde::Deserialize;
+use toml::{Spanned, Table};
+
+use crate::launcher::Launcher;
+
+pub use crate::registry::Registry;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FilterMode {
+    And,
+    Or,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct File {
+    batch_size: Option<usize>,
+    limit: Option<usize>,
+    filter_mode: Option<FilterMode>,
+
+    #[serde(default)]
+    source: Vec<Spanned<Table>>,
+    #[serde(default)]
+    filter: Vec<Spanned<Table>>,
+    #[serde(default)]
+    sorter: Vec<Spanned<Table>>,
+    #[serde(default)]
+    scorer: Vec<Spanned<Table>>,
+    #[serde(default)]
+    generator: Vec<Spanned<Table>>,
+    #[serde(default)]
+    action: Vec<Spanned<Table>>,
+}
+
+/// A parsed config file
+#[derive(Debug)]
+pub struct Config {
+    text: String,
+    file: File,
+}
+
+impl std::str::FromStr for Config {
+    type Err = color_eyre::Report;
+
+    fn from_str(text: &str) -> Result<Self> {
+        // toml's error already contains the line and the column
+        let file = toml::from_str(text).map_err(|e| eyre!("{e}"))?;
+
+        Ok(Self {
+            text: text.into(),
+            file,
+        })
+    }
+}
+
+impl Config {
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let path = path.as_ref();
+        std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?
+            .parse()
+            .wrap_err_with(|| format!("failed to parse {}", path.display()))
+    }
+
+    /// Builds a launcher. The UI has to be set after this.
+    pub fn build<Cushion>(&self, registry: &Registry<Cushion>) -> Result<Launcher<Cushion>>
+    where
+        Cushion: Send + Sync + 'static,
+    {
+        self.apply(Launcher::default(), registry)
+    }
+
+    /// Adds the extensions and the options in the config to `launcher`
+    pub fn apply<Cushion, UIT, UIContext>(
+        &self,
+        mut launcher: Launcher<Cushion, UIT, UIContext>,
+        registry: &Registry<Cushion>,
+    ) -> Result<Launcher<Cushion, UIT, UIContext>>
+    where
+        UIContext: Send,
+        Cushion: Send + Sync + 'static,
+    {
+        let file = &self.file;
+
+        if let Some(batch_size) = file.batch_size {
+            launcher = launcher.batch_size(batch_size);
+        }
+        if let Some(limit) = file.limit {
+            launcher = launcher.limit(limit);
+        }
+        if let Some(mode) = file.filter_mode {
+            launcher = launcher.filter_and(matches!(mode, FilterMode::And));
+        }
+
+        for table in &file.source {
+            launcher = launcher.add_raw_source(
+                self.create(table, &[], |name, options| registry.source(name, options))?,
+            );
+        }
+        for table in &file.filter {
+            launcher = launcher.add_raw_filter(
+                self.create(table, &[], |name, options| registry.filter(name, options))?,
+            );
+        }
+        for table in &file.sorter {
+            launcher = launcher.add_raw_sorter(
+                self.create(table, &[], |name, options| registry.sorter(name, options))?,
+            );
+        }
+        for table in &file.scorer {
+            let weight = match table.get_ref().get("weight") {
+                Some(weight) => weight
+                    .as_float()
+                    .or_else(|| weight.as_integer().map(|i| i as f64))
+                    .ok_or_else(|| self.error(table, "`weight` must be a number"))?,
+                None => 1.0,
+            };
+            launcher = launcher.add_raw_scorer(
+                self.create(table, &["weight"], |name, options| {
+                    registry.scorer(name, options)
+                })?,
+                weight,
+            );
+        }
+        for table in &file.generator {
+            launcher = launcher.add_raw_generator(self.create(table, &[], |name, options| {
+                registry.generator(name, options)
+            })?);
+        }
+        for table in &file.action {
+            launcher = launcher.add_raw_action(
+                self.create(table, &[], |name, options| registry.action(name, options))?,
+            );
+        }
+
+        Ok(launcher)
+    }
+
+    /// Creates the extension by the `name` of the table.
+    /// The other keys except `reserved` (read by the config itself) are passed as the options.
+    fn create<T>(
+        &self,
+        table: &Spanned<Table>,
+        reserved: &[&str],
+        f: impl FnOnce(&str, &Table) -> Result<T>,
+    ) -> Result<T> {
+        let name = table
+            .get_ref()
+            .get("name")
+            .ok_or_else(|| self.error(table, "`name` is missing"))?
+            .as_str()
+            .ok_or_else(|| self.error(table, "`name` must be a string"))?;
+
+        let mut options = table.get_ref().clone();
+        options.remove("name");
+        for key in reserved {
+            options.remove(*key);
+        }
+
+        f(name, &options).map_err(|e| self.error(table, format!("{e:#}")))
+    }
+
+    fn error<T>(&self, at: &Spanned<T>, msg: impl std::fmt::Display) -> color_eyre::Report {
+        let (line, column) = line_column(&self.text, at.span().start);
+        eyre!("{msg} at line {line}, column {column}")
+    }
+}
+
+/// 1-based line and column of the byte offset
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+
+    (line, column)
+}
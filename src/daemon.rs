@@ -0,0 +1,475 @@
+//! A server mode that keeps the sources warm, and thin clients that talk to it over a Unix socket.
+//!
+//! The server builds the launcher once, reads all of its sources and keeps the items in memory,
+//! so that each client only pays for filtering and sorting.
+//! The selection is made by the client, and the actions of the launcher are run by the server.
+//!
+//! ```no_run
+//! # async fn f() -> ltrait::color_eyre::Result<()> {
+//! use ltrait::{
+//!     Launcher,
+//!     daemon::{Client, Refresh, Remote, Server},
+//!     filter::ClosureFilter,
+//!     source::from_iter,
+//! };
+//! use std::time::Duration;
+//!
+//! // server
+//! let server = Server::new(|| {
+//!     Ok(Launcher::default()
+//!         .add_source(from_iter(0..10000), std::convert::identity)
+//!         .add_raw_filter(ClosureFilter::new(|x: &i32, input| x.to_string().contains(input)))
+//!         .set_ui(Remote::default(), |x: &i32| x.to_string()))
+//! })
+//! .refresh(Refresh::Interval(Duration::from_secs(600)));
+//! tokio::spawn(server.serve("/tmp/ltrait.sock"));
+//!
+//! // client
+//! let mut client = Client::<String>::connect("/tmp/ltrait.sock").await?;
+//! let page = client.input("42").await?;
+//! client.select(page.items[0].id).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Protocol
+//!
+//! Each message is a JSON object on a line. A client sends
+//!
+//! - `{"type": "input", "input": "..."}`, and the server replies with
+//!   `{"type": "page", "items": [{"id": 0, "context": ...}, ...], "truncated": false, "generation": 0}`
+//!   where `context` is the `UIContext` of the item.
+//! - `{"type": "select", "id": 0, "generation": 0}` with the `generation` of the page the id came from,
+//!   and the server runs the actions on the item and replies with `{"type": "selected"}`.
+//!   The ids are only valid in their generation, which changes on every refresh, so a select
+//!   of an older generation fails instead of running the actions on another item.
+//!   Only the ids in the last page of the same connection can be selected.
+//!
+//! Each connection has its own query. The inputs of other clients don't change its page or its ids,
+//! but the requests are processed one at a time since the clients share the warm items.
+//!
+//! On failure, the server replies with `{"type": "error", "message": "..."}` instead.
+//! A client may close the connection at any time to cancel.
+
+use color_eyre::eyre::{OptionExt, Result, bail, ensure, eyre};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::collections::BTreeSet;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::action::Action;
+use crate::launcher::Launcher;
+use crate::launcher::batcher::Batcher;
+use crate::launcher::batcher::items::Scratch;
+use crate::ui::{Buffer, Selection, UI};
+
+type BuildF<Cushion, UIContext> =
+    dyn Fn() -> Result<Launcher<Cushion, Remote<UIContext>, UIContext>> + Send + Sync;
+
+/// When the server rebuilds the launcher to pick up changes in the sources
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Refresh {
+    /// Sources are read only once at startup
+    #[default]
+    Never,
+    /// Rebuilds the launcher in the background every period.
+    /// The old items are served until the new ones are ready.
+    Interval(Duration),
+}
+
+/// A placeholder UI for the launchers served by [`Server`].
+///
+/// The launcher is driven by the clients, so [`Launcher::run`] fails with this UI.
+/// The transformer given to [`Launcher::set_ui`] decides what is sent to the clients.
+pub struct Remote<UIContext>(PhantomData<fn() -> UIContext>);
+
+impl<UIContext> Default for Remote<UIContext> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<Cushion, UIContext> UI<Cushion> for Remote<UIContext>
+where
+    Cushion: Send + Sync + 'static,
+    UIContext: Send,
+{
+    type Context = UIContext;
+
+    async fn run(&self, _: Batcher<Cushion, Self::Context>) -> Result<Option<Selection<Cushion>>> {
+        bail!("a launcher with the Remote UI has to be served by daemon::Server")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Request {
+    Input { input: String },
+    Select { id: usize, generation: u64 },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Response<T> {
+    Page {
+        items: Vec<Item<T>>,
+        truncated: bool,
+        generation: u64,
+    },
+    Selected,
+    Error {
+        message: String,
+    },
+}
+
+/// An item sent to clients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Item<T> {
+    /// Pass this to [`Client::select`]
+    pub id: usize,
+    pub context: T,
+}
+
+/// The result of an input
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<Item<T>>,
+    /// See [`Batcher::is_truncated`]
+    pub truncated: bool,
+    /// The ids of the items are only valid in this generation. It changes on every refresh of the server.
+    pub generation: u64,
+}
+
+struct Warm<Cushion, UIContext> {
+    batcher: Batcher<Cushion, UIContext>,
+    actions: Vec<Box<dyn Action<Context = Cushion>>>,
+    generation: u64,
+}
+
+/// The query state of a connection. The batcher is shared, so each connection keeps what it needs from its last input.
+struct Session<Cushion> {
+    /// The ids in the last page
+    ids: BTreeSet<usize>,
+    /// The generation of the last page, None before the first input
+    generation: Option<u64>,
+    /// The items from generators for the last input, which other inputs would drop
+    scratch: Scratch<Cushion>,
+}
+
+impl<Cushion> Default for Session<Cushion> {
+    fn default() -> Self {
+        Self {
+            ids: BTreeSet::new(),
+            generation: None,
+            scratch: Scratch::default(),
+        }
+    }
+}
+
+/// Serves a launcher to [`Client`]s
+pub struct Server<Cushion, UIContext>
+where
+    Cushion: Send + Sync + 'static,
+    UIContext: Send,
+{
+    build: Arc<BuildF<Cushion, UIContext>>,
+    refresh: Refresh,
+}
+
+impl<Cushion, UIContext> Server<Cushion, UIContext>
+where
+    Cushion: Send + Sync + 'static,
+    UIContext: Serialize + Send + 'static,
+{
+    /// `build` is called at startup and on every refresh.
+    /// The UI of the launcher has to be set to [`Remote`].
+    pub fn new<F>(build: F) -> Self
+    where
+        F: Fn() -> Result<Launcher<Cushion, Remote<UIContext>, UIContext>> + Send + Sync + 'static,
+    {
+        Self {
+            build: Arc::new(build),
+            refresh: Refresh::default(),
+        }
+    }
+
+    pub fn refresh(mut self, refresh: Refresh) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Binds `path` and serves clients until an error occurs.
+    ///
+    /// The socket file is not removed, remove it before serving again.
+    pub async fn serve(self, path: impl AsRef<Path>) -> Result<()> {
+        let listener = UnixListener::bind(path)?;
+
+        let warm = Arc::new(Mutex::new(warm_up(&*self.build, 0).await?));
+        info!("Sources are warmed up");
+
+        let refresher = match self.refresh {
+            Refresh::Never => None,
+            Refresh::Interval(period) => {
+                let build = self.build.clone();
+                let warm = warm.clone();
+
+                Some(tokio::spawn(async move {
+                    let mut generation = 0;
+                    loop {
+                        tokio::time::sleep(period).await;
+
+                        // ロックを取らずに作ってから差し替える
+                        match warm_up(&*build, generation + 1).await {
+                            Ok(new) => {
+                                generation += 1;
+                                *warm.lock().await = new;
+                            }
+                            Err(e) => warn!("Failed to refresh the launcher: {e}"),
+                        }
+                    }
+                }))
+            }
+        };
+
+        let result = loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => break Err(e.into()),
+            };
+            let warm = warm.clone();
+
+            tokio::spawn(async move {
+                if let Err(e) = handle(stream, warm).await {
+                    warn!("Client error: {e}");
+                }
+            });
+        };
+
+        if let Some(refresher) = refresher {
+            refresher.abort();
+        }
+
+        result
+    }
+}
+
+/// Builds the launcher and reads all of its sources
+async fn warm_up<Cushion, UIContext>(
+    build: &BuildF<Cushion, UIContext>,
+    generation: u64,
+) -> Result<Warm<Cushion, UIContext>>
+where
+    Cushion: Send + Sync + 'static,
+    UIContext: Send,
+{
+    let (mut batcher, actions) = build()?.into_parts();
+    merge_all(&mut batcher, &mut Buffer::default()).await;
+
+    Ok(Warm {
+        batcher,
+        actions,
+        generation,
+    })
+}
+
+/// Runs all the batches. The errors of the try sources are logged, and the items from the others are kept.
+async fn merge_all<Cushion, UIContext>(
+    batcher: &mut Batcher<Cushion, UIContext>,
+    buf: &mut Buffer<(UIContext, usize)>,
+) where
+    Cushion: Send,
+{
+    let mut more = true;
+    while more {
+        let from = batcher.prepare().await;
+        more = batcher.merge(buf, from).unwrap_or_else(|e| {
+            warn!("Source error: {e}");
+            // errorの後もsourceは読み続けられる
+            true
+        });
+    }
+}
+
+async fn handle<Cushion, UIContext>(
+    stream: UnixStream,
+    warm: Arc<Mutex<Warm<Cushion, UIContext>>>,
+) -> Result<()>
+where
+    Cushion: Send + Sync + 'static,
+    UIContext: Serialize + Send,
+{
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    let mut session = Session::default();
+
+    while let Some(line) = lines.next_line().await? {
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => {
+                let mut warm = warm.lock().await;
+                respond(&mut warm, &mut session, request).await
+            }
+            Err(e) => Err(eyre!("invalid request: {e}")),
+        }
+        .unwrap_or_else(|e| Response::Error {
+            message: format!("{e}"),
+        });
+
+        let mut json = serde_json::to_vec(&response)?;
+        json.push(b'\n');
+        write.write_all(&json).await?;
+    }
+
+    Ok(())
+}
+
+async fn respond<Cushion, UIContext>(
+    warm: &mut Warm<Cushion, UIContext>,
+    session: &mut Session<Cushion>,
+    request: Request,
+) -> Result<Response<UIContext>>
+where
+    Cushion: Send + Sync + 'static,
+    UIContext: Send,
+{
+    match request {
+        Request::Input { input } => {
+            let batcher = &mut warm.batcher;
+            let mut buf = Buffer::default();
+
+            batcher.input(&mut buf, &input);
+            merge_all(batcher, &mut buf).await;
+
+            // 他の接続のinputで消されないように持っておく
+            session.scratch = batcher.take_scratch();
+            session.ids = buf.as_slice().iter().map(|(_, id)| *id).collect();
+            session.generation = Some(warm.generation);
+
+            Ok(Response::Page {
+                items: buf
+                    .into_inner()
+                    .into_iter()
+                    .map(|(context, id)| Item { id, context })
+                    .collect(),
+                truncated: batcher.is_truncated(),
+                generation: warm.generation,
+            })
+        }
+        Request::Select { id, generation } => {
+            ensure!(
+                generation == warm.generation && session.generation == Some(generation),
+                "the items have been refreshed, send the input again"
+            );
+            ensure!(
+                session.ids.contains(&id),
+                "the item is not in the last page"
+            );
+
+            // generatorのitemのidはこの接続のinputのもの
+            warm.batcher.swap_scratch(&mut session.scratch);
+            let result = warm
+                .batcher
+                .cushion(id)
+                .ok_or_eyre("the item does not exist")
+                .and_then(|cushion| {
+                    warm.actions
+                        .iter()
+                        .try_for_each(|action| action.act(cushion))
+                });
+            warm.batcher.swap_scratch(&mut session.scratch);
+
+            result.map(|_| Response::Selected)
+        }
+    }
+}
+
+/// A client of [`Server`]. `T` is the `UIContext` of the served launcher.
+pub struct Client<T> {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    write: OwnedWriteHalf,
+    /// The generation of the last page
+    generation: u64,
+
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Client<T>
+where
+    T: DeserializeOwned,
+{
+    pub async fn connect(path: impl AsRef<Path>) -> Result<Self> {
+        let (read, write) = UnixStream::connect(path).await?.into_split();
+
+        Ok(Self {
+            lines: BufReader::new(read).lines(),
+            write,
+            generation: 0,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Sends the input and returns the ranked items
+    pub async fn input(&mut self, input: &str) -> Result<Page<T>> {
+        match self
+            .request(&Request::Input {
+                input: input.into(),
+            })
+            .await?
+        {
+            Response::Page {
+                items,
+                truncated,
+                generation,
+            } => {
+                self.generation = generation;
+                Ok(Page {
+                    items,
+                    truncated,
+                    generation,
+                })
+            }
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Selects the item of `id` from the last page and waits for the actions to finish.
+    /// It fails if the server has been refreshed after the page.
+    pub async fn select(&mut self, id: usize) -> Result<()> {
+        let request = Request::Select {
+            id,
+            generation: self.generation,
+        };
+        match self.request(&request).await? {
+            Response::Selected => Ok(()),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    async fn request(&mut self, request: &Request) -> Result<Response<T>> {
+        let mut json = serde_json::to_vec(request)?;
+        json.push(b'\n');
+        self.write.write_all(&json).await?;
+
+        let line = self
+            .lines
+            .next_line()
+            .await?
+            .ok_or_eyre("the server closed the connection")?;
+
+        Ok(serde_json::from_str(&line)?)
+    }
+}
+
+fn unexpected<T>(response: Response<T>) -> color_eyre::Report {
+    match response {
+        Response::Error { message } => eyre!(message),
+        _ => eyre!("unexpected response from the server"),
+    }
+}
@@ -0,0 +1,252 @@
+//! Splits source lines into fields, like `--nth` and `--with-nth` of fzf.
+//!
+//! Items are kept as [`Fields`], so filters, sorters and the UI can each look at different fields
+//! through their transformers while actions still receive the whole original line.
+//!
+//! ```
+//! use ltrait::field::{Delimiter, FieldSpec, Fields};
+//!
+//! let fields = Fields::new("1234 ?? 00:00:01 /usr/bin/foo --bar", &Delimiter::Whitespace);
+//!
+//! let nth: FieldSpec = "4..".parse().unwrap();
+//! let with_nth: FieldSpec = "1,-2".parse().unwrap();
+//!
+//! assert_eq!(fields.select(&nth), "/usr/bin/foo --bar");
+//! assert_eq!(fields.select(&with_nth), "1234 /usr/bin/foo");
+//! assert_eq!(fields.line(), "1234 ?? 00:00:01 /usr/bin/foo --bar");
+//! ```
+
+use color_eyre::eyre::{Result, bail, ensure};
+use std::ops::Range;
+
+/// How a line is split into fields
+#[derive(Debug, Clone)]
+pub enum Delimiter {
+    /// Splits by runs of whitespace and ignores leading and trailing whitespace, like awk
+    Whitespace,
+    /// Splits by the string
+    Str(String),
+    /// Splits by the matches of the regex
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl Delimiter {
+    fn split(&self, line: &str) -> Vec<Range<usize>> {
+        let base = line.as_ptr() as usize;
+        let range = |field: &str| {
+            let start = field.as_ptr() as usize - base;
+            start..start + field.len()
+        };
+
+        match self {
+            Self::Whitespace => line.split_whitespace().map(range).collect(),
+            Self::Str(delimiter) => line.split(delimiter.as_str()).map(range).collect(),
+            #[cfg(feature = "regex")]
+            Self::Regex(regex) => regex.split(line).map(range).collect(),
+        }
+    }
+}
+
+/// A line and the ranges of its fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fields {
+    line: String,
+    ranges: Vec<Range<usize>>,
+}
+
+impl Fields {
+    pub fn new(line: impl Into<String>, delimiter: &Delimiter) -> Self {
+        let line = line.into();
+        let ranges = delimiter.split(&line);
+
+        Self { line, ranges }
+    }
+
+    /// The original line
+    #[inline]
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    #[inline]
+    pub fn into_line(self) -> String {
+        self.line
+    }
+
+    /// The number of fields
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Returns the field at `index` (0-based)
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.ranges.get(index).map(|r| &self.line[r.clone()])
+    }
+
+    /// Returns the fields chosen by `spec`.
+    ///
+    /// A range of fields is taken from the line as is (with the delimiters between them),
+    /// and the parts of the spec are joined with a space.
+    pub fn select(&self, spec: &FieldSpec) -> String {
+        spec.parts
+            .iter()
+            .filter_map(|part| {
+                let (start, end) = part.resolve(self.len())?;
+                Some(&self.line[self.ranges[start].start..self.ranges[end].end])
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// A transformer that splits the lines from a source into [`Fields`]
+///
+/// ```
+/// use ltrait::{Launcher, field::{self, Delimiter, Fields}, source::from_iter};
+/// # struct Ui;
+/// # impl ltrait::UI<Fields> for Ui {
+/// #     type Context = ();
+/// #     async fn run(&self, _: ltrait::launcher::batcher::Batcher<Fields, ()>) -> ltrait::color_eyre::Result<Option<ltrait::ui::Selection<Fields>>> { Ok(None) }
+/// # }
+///
+/// let launcher = Launcher::default()
+///     .add_source(from_iter(["a\tb"]), field::splitter(Delimiter::Str("\t".into())))
+/// #   .set_ui(Ui, |_| ())
+///     ;
+/// ```
+pub fn splitter<S>(delimiter: Delimiter) -> impl Fn(S) -> Fields + Send + Sync + 'static
+where
+    S: Into<String>,
+{
+    move |line| Fields::new(line, &delimiter)
+}
+
+/// A transformer that returns the fields chosen by `spec`, for filters, sorters and the UI
+pub fn selector(spec: FieldSpec) -> impl Fn(&Fields) -> String + Send + Sync + 'static {
+    move |fields| fields.select(&spec)
+}
+
+/// Which fields to use, written like `--nth` of fzf.
+///
+/// The spec is a comma-separated list of field indices (1-based, negative indices count from the last field)
+/// and ranges of them (`N..M`, `N..`, `..M` and `..`).
+/// For example, `2`, `-1`, `1,3..` and `..-2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSpec {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Part {
+    start: Option<isize>,
+    end: Option<isize>,
+}
+
+impl Part {
+    /// Returns the inclusive range of 0-based indices, or None if it is out of the fields
+    fn resolve(&self, len: usize) -> Option<(usize, usize)> {
+        let index = |i: isize| {
+            if i > 0 { i - 1 } else { len as isize + i }
+        };
+
+        let start = self.start.map_or(0, index).max(0);
+        let end = self
+            .end
+            .map_or(len as isize - 1, index)
+            .min(len as isize - 1);
+
+        (start <= end).then_some((start as usize, end as usize))
+    }
+}
+
+impl std::str::FromStr for FieldSpec {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        fn index(s: &str, spec: &str) -> Result<Option<isize>> {
+            if s.is_empty() {
+                return Ok(None);
+            }
+
+            let i: isize = s.parse()?;
+            ensure!(i != 0, "field index starts from 1: `{spec}`");
+            Ok(Some(i))
+        }
+
+        let parts = s
+            .split(',')
+            .map(|part| {
+                let part = part.trim();
+                if part.is_empty() {
+                    bail!("empty field in `{s}`");
+                }
+
+                Ok(match part.split_once("..") {
+                    Some((start, end)) => Part {
+                        start: index(start, s)?,
+                        end: index(end, s)?,
+                    },
+                    None => {
+                        let i = index(part, s)?;
+                        Part { start: i, end: i }
+                    }
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { parts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select() -> Result<()> {
+        let fields = Fields::new("  a b  c d ", &Delimiter::Whitespace);
+        assert_eq!(fields.len(), 4);
+
+        let select = |spec: &str| -> Result<String> { Ok(fields.select(&spec.parse()?)) };
+
+        assert_eq!(select("1")?, "a");
+        assert_eq!(select("-1")?, "d");
+        assert_eq!(select("2..3")?, "b  c");
+        assert_eq!(select("..2,-1")?, "a b d");
+        assert_eq!(select("3..")?, "c d");
+        assert_eq!(select("..")?, "a b  c d");
+        assert_eq!(select("5")?, "");
+
+        assert!("0".parse::<FieldSpec>().is_err());
+        assert!("1,,2".parse::<FieldSpec>().is_err());
+        assert!("a".parse::<FieldSpec>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn delimiter() {
+        let fields = Fields::new("a\t\tb", &Delimiter::Str("\t".into()));
+        assert_eq!(fields.get(0), Some("a"));
+        assert_eq!(fields.get(1), Some(""));
+        assert_eq!(fields.get(2), Some("b"));
+        assert_eq!(fields.line(), "a\t\tb");
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex() -> Result<()> {
+        let fields = Fields::new("a1b22c", &Delimiter::Regex(regex::Regex::new("[0-9]+")?));
+        assert_eq!(fields.select(&"1,3".parse()?), "a c");
+
+        Ok(())
+    }
+}
@@ -6,6 +6,28 @@ pub trait Filter: Send {
     type Context;
 
     fn predicate(&self, ctx: &Self::Context, input: &str) -> bool;
+
+    /// Decides all the items of a batch at once.
+    ///
+    /// Override this when each call has a fixed cost (e.g. IPC). The result must have the same length as `ctxs`.
+    fn predicate_batch(&self, ctxs: &[&Self::Context], input: &str) -> Vec<bool> {
+        ctxs.iter().map(|ctx| self.predicate(ctx, input)).collect()
+    }
+}
+
+impl<T> Filter for Box<T>
+where
+    T: Filter + ?Sized,
+{
+    type Context = T::Context;
+
+    fn predicate(&self, ctx: &Self::Context, input: &str) -> bool {
+        (**self).predicate(ctx, input)
+    }
+
+    fn predicate_batch(&self, ctxs: &[&Self::Context], input: &str) -> Vec<bool> {
+        (**self).predicate_batch(ctxs, input)
+    }
 }
 
 pub struct ClosureFilter<Context, F>(F, PhantomData<Context>)
@@ -57,6 +79,12 @@ where
     fn predicate(&self, ctx: &Self::Context, input: &str) -> bool {
         self.filter.predicate(&(self.f)(ctx), input)
     }
+
+    fn predicate_batch(&self, ctxs: &[&Self::Context], input: &str) -> Vec<bool> {
+        let ctxs: Vec<_> = ctxs.iter().map(|ctx| (self.f)(ctx)).collect();
+        self.filter
+            .predicate_batch(&ctxs.iter().collect::<Vec<_>>(), input)
+    }
 }
 
 impl<FilterContext, FilterT, F, Cushion> FilterWrapper<FilterContext, FilterT, F, Cushion>
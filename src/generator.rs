@@ -13,6 +13,18 @@ pub trait Generator: Send + Sync {
     async fn generate(&self, input: &str) -> Vec<Self::Item>;
 }
 
+#[async_trait]
+impl<T> Generator for Box<T>
+where
+    T: Generator + ?Sized,
+{
+    type Item = T::Item;
+
+    async fn generate(&self, input: &str) -> Vec<Self::Item> {
+        (**self).generate(input).await
+    }
+}
+
 pub struct ClosureGenerator<Item, F>(F, PhantomData<Item>)
 where
     F: Fn(&str) -> Vec<Item>;
@@ -1,38 +1,51 @@
-use color_eyre::eyre::{OptionExt, Result};
+use color_eyre::eyre::Result;
 
 use crate::action::{Action, ActionWrapper};
 use crate::filter::{Filter, FilterWrapper};
 use crate::generator::{GenWrapper, Generator};
 use crate::launcher::batcher::Batcher;
+use crate::launcher::dedup::{DedupPolicy, Deduplicator};
+use crate::scorer::{Scorer, ScorerWrapper};
 use crate::sorter::{Sorter, SorterWrapper};
-use crate::source::{Source, transform_source};
-use crate::ui::UI;
+use crate::source::{
+    LiveSource, Source, transform_live_source, transform_source, transform_try_source,
+};
+use crate::ui::{Selection, UI};
 
 pub mod batcher;
+pub mod dedup;
 
-pub struct Launcher<Cushion, UIT, UIContext>
+#[cfg(all(unix, feature = "daemon"))]
+type Parts<Cushion, UIContext> = (Batcher<Cushion, UIContext>, Vec<batcher::ActionT<Cushion>>);
+
+/// The UI of a launcher before [`Launcher::set_ui`] is called. [`Launcher::run`] can't be called with it.
+///
+/// ```compile_fail
+/// # async fn f() -> ltrait::color_eyre::Result<()> {
+/// ltrait::Launcher::<()>::default().run().await
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoUI;
+
+pub struct Launcher<Cushion, UIT = NoUI, UIContext = ()>
 where
-    UIT: UI<Cushion, Context = UIContext>,
     UIContext: Send,
     Cushion: Sync + Send + 'static,
 {
     batcher: Batcher<Cushion, UIContext>,
 
-    actions: Vec<Box<dyn Action<Context = Cushion>>>,
-    ui: Option<UIT>,
+    ui: UIT,
 }
 
-impl<Cushion, UIT, UIContext> Default for Launcher<Cushion, UIT, UIContext>
+impl<Cushion> Default for Launcher<Cushion, NoUI, ()>
 where
-    UIT: UI<Cushion, Context = UIContext>,
-    UIContext: Send,
     Cushion: Sync + Send,
 {
     fn default() -> Self {
         Self {
             batcher: batcher::Batcher::default(),
-            actions: vec![],
-            ui: None,
+            ui: NoUI,
         }
     }
 }
@@ -51,7 +64,6 @@ where
 /// as the transformer function
 impl<Cushion, UIT, UIContext> Launcher<Cushion, UIT, UIContext>
 where
-    UIT: UI<Cushion, Context = UIContext>,
     UIContext: Send,
     Cushion: Send + Sync + 'static,
 {
@@ -68,6 +80,50 @@ where
         self
     }
 
+    /// Adds a source that can fail. An error from the source is returned from the next
+    /// [`Batcher::merge`] (and so usually from [`Launcher::run`]), and the source is read on after that.
+    pub fn add_try_source<SourceContext, F>(
+        self,
+        source: Source<Result<SourceContext>>,
+        transformer: F,
+    ) -> Self
+    where
+        F: Fn(SourceContext) -> Cushion + Send + 'static,
+        SourceContext: 'static,
+    {
+        self.add_raw_try_source(transform_try_source(source, transformer))
+    }
+
+    pub fn add_raw_try_source(mut self, source: Source<Result<Cushion>>) -> Self {
+        self.batcher.add_raw_try_source(source);
+        self
+    }
+
+    /// Adds a source that can replace and remove its items while the launcher runs.
+    /// See [`LiveSource`] for details.
+    ///
+    /// Items from live sources are not deduplicated.
+    pub fn add_live_source<Id, SourceContext, F>(
+        self,
+        source: LiveSource<Id, SourceContext>,
+        transformer: F,
+    ) -> Self
+    where
+        Id: Ord + Send + 'static,
+        F: Fn(SourceContext) -> Cushion + Send + 'static,
+        SourceContext: 'static,
+    {
+        self.add_raw_live_source(transform_live_source(source, transformer))
+    }
+
+    pub fn add_raw_live_source<Id>(mut self, source: LiveSource<Id, Cushion>) -> Self
+    where
+        Id: Ord + Send + 'static,
+    {
+        self.batcher.add_raw_live_source(source);
+        self
+    }
+
     pub fn add_filter<FilterContext, FilterT, F>(self, filter: FilterT, transformer: F) -> Self
     where
         FilterT: Filter<Context = FilterContext> + 'static,
@@ -102,6 +158,32 @@ where
         self
     }
 
+    /// Adds a scorer whose score is multiplied by `weight` before being summed with the other scorers.
+    ///
+    /// Items are ordered by the total score (higher first). Sorters are only consulted to break ties,
+    /// so without any scorer the order is decided by the sorters alone.
+    pub fn add_scorer<ScorerContext, ScorerT, F>(
+        self,
+        scorer: ScorerT,
+        weight: f64,
+        transformer: F,
+    ) -> Self
+    where
+        ScorerT: Scorer<Context = ScorerContext> + 'static,
+        ScorerContext: Sync + Send + 'static,
+        F: Fn(&Cushion) -> ScorerContext + Send + 'static,
+    {
+        self.add_raw_scorer(ScorerWrapper::new(scorer, transformer), weight)
+    }
+
+    pub fn add_raw_scorer<ScorerT>(mut self, scorer: ScorerT, weight: f64) -> Self
+    where
+        ScorerT: Scorer<Context = Cushion> + 'static,
+    {
+        self.batcher.add_raw_scorer(scorer, weight);
+        self
+    }
+
     pub fn add_action<ActionContext: 'static, ActionT, F>(
         self,
         action: ActionT,
@@ -114,24 +196,64 @@ where
         self.add_raw_action(ActionWrapper::new(action, transformer))
     }
 
-    pub fn add_raw_action<ActionT>(mut self, action: ActionT) -> Self
+    pub fn add_raw_action<ActionT>(self, action: ActionT) -> Self
     where
         ActionT: Action<Context = Cushion> + 'static,
     {
-        self.actions.push(Box::new(action));
+        self.batcher
+            .actions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Box::new(action));
 
         self
     }
 
-    pub fn set_ui<F>(mut self, ui: UIT, transformer: F) -> Self
+    /// Adds an action on the raw input, run when the UI returns [`Selection::Query`]
+    /// (e.g. to run the input as a shell command, or to search for it on the web).
+    /// The actions added by [`Launcher::add_raw_action`] are only run on the items.
+    pub fn add_fallback_action<ActionContext: 'static, ActionT, F>(
+        self,
+        action: ActionT,
+        transformer: F,
+    ) -> Self
+    where
+        ActionT: Action<Context = ActionContext> + 'static,
+        F: Fn(&String) -> ActionContext + Send + 'static,
+    {
+        self.add_raw_fallback_action(ActionWrapper::new(action, transformer))
+    }
+
+    pub fn add_raw_fallback_action<ActionT>(self, action: ActionT) -> Self
     where
-        F: Fn(&Cushion) -> UIContext + Send + Sync + 'static,
+        ActionT: Action<Context = String> + 'static,
     {
-        self.ui = Some(ui);
-        self.batcher.cushion_to_ui = Some(Box::new(transformer));
+        self.batcher
+            .fallback_actions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Box::new(action));
+
         self
     }
 
+    /// Sets (or replaces) the UI. [`Launcher::run`] can be called after this.
+    pub fn set_ui<NewUI, NewContext, F>(
+        self,
+        ui: NewUI,
+        transformer: F,
+    ) -> Launcher<Cushion, NewUI, NewContext>
+    where
+        NewUI: UI<Cushion, Context = NewContext>,
+        NewContext: Send,
+        F: Fn(&Cushion) -> NewContext + Send + Sync + 'static,
+    {
+        Launcher {
+            batcher: self.batcher.with_ui(Box::new(transformer)),
+            ui,
+        }
+    }
+
     pub fn add_generator<Item, GenT, F>(self, generator: GenT, transformer: F) -> Self
     where
         GenT: Generator<Item = Item> + Sync + Send + 'static,
@@ -149,20 +271,17 @@ where
         self
     }
 
-    pub async fn run(self) -> Result<()> {
-        let cushion: Option<Cushion> = self
-            .ui
-            .ok_or_eyre("UI must be set before calling run")?
-            .run(self.batcher)
-            .await?;
-
-        if let Some(cushion) = cushion {
-            for ai in self.actions {
-                ai.act(&cushion)?;
-            }
-        }
-
-        Ok(())
+    /// Splits the launcher into the batcher and the actions, for drivers other than [`Launcher::run`]
+    #[cfg(all(unix, feature = "daemon"))]
+    pub(crate) fn into_parts(self) -> Parts<Cushion, UIContext> {
+        let actions = std::mem::take(
+            &mut *self
+                .batcher
+                .actions
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()),
+        );
+        (self.batcher, actions)
     }
 
     /// If `filter_and` is true and more than one filter is provided,
@@ -194,4 +313,142 @@ where
         self.batcher.batch_size = batch_size;
         self
     }
+
+    /// Removes duplicated items from sources. Two items are duplicates when `key` returns the same value for them,
+    /// and `policy` decides which one is kept.
+    ///
+    /// Items from generators are not deduplicated.
+    pub fn dedup<K, F>(mut self, key: F, policy: DedupPolicy<Cushion>) -> Self
+    where
+        K: Ord + Send + 'static,
+        F: Fn(&Cushion) -> K + Send + 'static,
+    {
+        self.batcher.dedup = Some(Box::new(Deduplicator::new(key, policy)));
+        self
+    }
+
+    /// Measures the time spent in each source, generator, filter, scorer and sorter, and in merge.
+    /// The UI can read them by [`Batcher::metrics`] after each prepare and merge.
+    ///
+    /// The default value is false, since measuring every comparison of the sorters has some overhead.
+    pub fn collect_metrics(mut self, flag: bool) -> Self {
+        self.batcher.metrics = flag.then(Default::default);
+        self
+    }
+
+    /// Keeps only the best `limit` items (according to the scorers and sorters) in the rendering buffer.
+    ///
+    /// Since a UI only shows a limited number of rows, this avoids sorting and merging every item.
+    /// The UI can get more rows later with [`Batcher::extend_limit`].
+    ///
+    /// When `limit` is set to 0, all items are kept.
+    /// The default value is 0.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.batcher.limit = limit;
+        self
+    }
+
+    /// Keeps the launcher open after an action, e.g. for a dock-like launcher.
+    ///
+    /// The UI runs the actions by [`Batcher::act_and_reset`] and shows the results of an empty input again,
+    /// without collecting the sources again, until an action returns [`Flow::Close`](crate::action::Flow::Close).
+    /// The UI has to support this, see [`Batcher::keep_open`].
+    /// The default value is false.
+    pub fn keep_open(mut self, flag: bool) -> Self {
+        self.batcher.keep_open = flag;
+        self
+    }
+}
+
+impl<Cushion, UIT, UIContext> Launcher<Cushion, UIT, UIContext>
+where
+    UIT: UI<Cushion, Context = UIContext>,
+    UIContext: Send,
+    Cushion: Send + Sync + 'static,
+{
+    pub async fn run(self) -> Result<()> {
+        let actions = std::sync::Arc::clone(&self.batcher.actions);
+        let fallback_actions = std::sync::Arc::clone(&self.batcher.fallback_actions);
+
+        match self.ui.run(self.batcher).await? {
+            Some(Selection::Item(cushion)) => {
+                let actions = actions.lock().unwrap_or_else(|e| e.into_inner());
+                for ai in actions.iter() {
+                    ai.act(&cushion)?;
+                }
+            }
+            Some(Selection::Query(query)) => {
+                let actions = fallback_actions.lock().unwrap_or_else(|e| e.into_inner());
+                for ai in actions.iter() {
+                    ai.act(&query)?;
+                }
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Adds extensions by the names registered in a [`Registry`](crate::registry::Registry)
+#[cfg(feature = "registry")]
+impl<Cushion, UIT, UIContext> Launcher<Cushion, UIT, UIContext>
+where
+    UIContext: Send,
+    Cushion: Send + Sync + 'static,
+{
+    pub fn add_named_source(
+        self,
+        registry: &crate::registry::Registry<Cushion>,
+        name: &str,
+        options: &toml::Table,
+    ) -> Result<Self> {
+        Ok(self.add_raw_source(registry.source(name, options)?))
+    }
+
+    pub fn add_named_filter(
+        self,
+        registry: &crate::registry::Registry<Cushion>,
+        name: &str,
+        options: &toml::Table,
+    ) -> Result<Self> {
+        Ok(self.add_raw_filter(registry.filter(name, options)?))
+    }
+
+    pub fn add_named_sorter(
+        self,
+        registry: &crate::registry::Registry<Cushion>,
+        name: &str,
+        options: &toml::Table,
+    ) -> Result<Self> {
+        Ok(self.add_raw_sorter(registry.sorter(name, options)?))
+    }
+
+    pub fn add_named_scorer(
+        self,
+        registry: &crate::registry::Registry<Cushion>,
+        name: &str,
+        weight: f64,
+        options: &toml::Table,
+    ) -> Result<Self> {
+        Ok(self.add_raw_scorer(registry.scorer(name, options)?, weight))
+    }
+
+    pub fn add_named_generator(
+        self,
+        registry: &crate::registry::Registry<Cushion>,
+        name: &str,
+        options: &toml::Table,
+    ) -> Result<Self> {
+        Ok(self.add_raw_generator(registry.generator(name, options)?))
+    }
+
+    pub fn add_named_action(
+        self,
+        registry: &crate::registry::Registry<Cushion>,
+        name: &str,
+        options: &toml::Table,
+    ) -> Result<Self> {
+        Ok(self.add_raw_action(registry.action(name, options)?))
+    }
 }
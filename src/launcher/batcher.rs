@@ -1,51 +1,92 @@
-use color_eyre::eyre::{Result, ensure, eyre};
+use color_eyre::eyre::{OptionExt, Result};
 
 use tracing::{debug, info};
 
+use crate::action::{Action, Flow};
 use crate::filter::Filter;
 use crate::generator::Generator;
+use crate::launcher::dedup::{Dedup, DedupOutcome};
+use crate::scorer::Scorer;
 use crate::sorter::Sorter;
-use crate::source::Source;
+use crate::source::{LiveSource, Source};
 
 use crate::ui::{Buffer, Position};
 
 use tokio_stream::StreamExt as _;
 
-type CushionToUIF<Cushion, UIContext> = Option<Box<dyn Fn(&Cushion) -> UIContext + Send>>;
+use std::cell::Cell;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub(crate) mod items;
+mod live;
+pub mod metrics;
+
+use items::Items;
+use live::{Applied, Live, LiveState};
+use metrics::{Metrics, Stage};
+
+type CushionToUIF<Cushion, UIContext> = Box<dyn Fn(&Cushion) -> UIContext + Send>;
 
 type FilterT<Cushion> = Box<dyn Filter<Context = Cushion>>;
 type SorterT<Cushion> = Box<dyn Sorter<Context = Cushion>>;
+type ScorerT<Cushion> = Box<dyn Scorer<Context = Cushion>>;
 type GenT<Cushion> = Box<dyn Generator<Item = Cushion>>;
+pub(super) type ActionT<Cushion> = Box<dyn Action<Context = Cushion>>;
+
+const NO_ITEM: &str = "Failed to get Cushion, the item does not exist. Maybe the ui is not using the usize obtained from Buffer, \
+    or the item has been removed by a live source or a new input";
 
 pub struct Batcher<Cushion, UIContext> {
     filters: Vec<FilterT<Cushion>>,
     sorters: Vec<SorterT<Cushion>>,
+    /// scorer and its weight
+    scorers: Vec<(ScorerT<Cushion>, f64)>,
     generators: Vec<GenT<Cushion>>,
-    sources: Vec<Source<Cushion>>,
+    /// Sources added by `add_raw_source` are wrapped with `Ok`
+    sources: Vec<Source<Result<Cushion>>>,
+    live_sources: Vec<Box<dyn Live<Cushion>>>,
+    /// Shared with the launcher, which runs them on the selected item after the UI returns
+    pub(super) actions: Arc<Mutex<Vec<ActionT<Cushion>>>>,
+    /// Actions on the raw input, shared in the same way
+    pub(super) fallback_actions: Arc<Mutex<Vec<ActionT<String>>>>,
 
     pub(super) cushion_to_ui: CushionToUIF<Cushion, UIContext>,
+    pub(super) dedup: Option<Box<dyn Dedup<Cushion>>>,
 
     pub(super) batch_size: usize,
     pub(super) filter_and: bool,
+    pub(super) limit: usize,
+    pub(super) keep_open: bool,
+
+    /// None if metrics are not collected
+    pub(super) metrics: Option<Metrics>,
 
     state: BatcherState<Cushion>,
 }
 
-impl<Cushion, UIContext> Default for Batcher<Cushion, UIContext>
-where
-    UIContext: Send,
-{
+/// A batcher without a UI, which renders every item as `()`
+impl<Cushion> Default for Batcher<Cushion, ()> {
     fn default() -> Self {
         Self {
             filters: vec![],
             sorters: vec![],
+            scorers: vec![],
             sources: vec![],
             generators: vec![],
+            live_sources: vec![],
+            actions: Arc::default(),
+            fallback_actions: Arc::default(),
 
             batch_size: 0,
             filter_and: true,
+            limit: 0,
+            keep_open: false,
 
-            cushion_to_ui: None,
+            metrics: None,
+
+            cushion_to_ui: Box::new(|_| ()),
+            dedup: None,
 
             state: BatcherState::default(),
         }
@@ -57,9 +98,10 @@ struct BatcherState<Cushion> {
 
     /// Items sourced from Source and generators when first batch
     /// The cache of the second and subsequent times is used.
+    /// Items from generators are dropped on every input.
     ///
     /// And Buffer's usize is `sourced_items`'s index
-    items: Vec<Cushion>,
+    items: Items<Cushion>,
 
     // index of items
     items_from_sources_i: (Buffer<usize>, Position),
@@ -71,6 +113,12 @@ struct BatcherState<Cushion> {
 
     gen_index: usize,
     source_index: usize,
+
+    /// Whether some items were dropped because of `limit` in the current input
+    truncated: bool,
+
+    /// Errors from sources, returned by the next merge
+    errors: Vec<color_eyre::Report>,
 }
 
 mod debug_state {
@@ -96,6 +144,8 @@ mod debug_state {
                 .field("first_source", &self.first_source)
                 .field("gen_index", &self.gen_index)
                 .field("source_index", &self.source_index)
+                .field("truncated", &self.truncated)
+                .field("errors", &self.errors.len())
                 .finish()
         }
     }
@@ -107,23 +157,43 @@ impl<Cushion> Default for BatcherState<Cushion> {
             input: "".into(),
             gen_index: 0,
             source_index: 0,
+            truncated: false,
+            errors: vec![],
             first_source: true,
             peeked_item: None,
-            items: vec![],
+            items: Items::default(),
             items_from_sources_i: (Buffer::default(), Position::default()),
         }
     }
 }
 
-pub struct Prepared<T>(Buffer<(T, usize)>);
+/// What [`Batcher::merge_with_changes`] changed in the rendering buffer
+#[derive(Debug, Default)]
+pub(crate) struct Changes {
+    /// ids of the items removed from the buffer
+    pub(crate) removed: Vec<usize>,
+    /// Indices of the inserted items in the buffer after the merge, in ascending order
+    pub(crate) inserted: Vec<usize>,
+}
+
+pub struct Prepared<T> {
+    buf: Buffer<(T, usize)>,
+
+    /// ids of items replaced after they were merged into the rendering buffer.
+    /// They are in `buf` again if they still pass the filters.
+    invalidated: Vec<usize>,
+}
 
 impl<T> Prepared<T> {
     pub(crate) fn into_inner(self) -> Buffer<(T, usize)> {
-        self.0
+        self.buf
     }
 
-    pub(crate) fn new(value: Buffer<(T, usize)>) -> Self {
-        Self(value)
+    pub(crate) fn new(value: Buffer<(T, usize)>, invalidated: Vec<usize>) -> Self {
+        Self {
+            buf: value,
+            invalidated,
+        }
     }
 }
 
@@ -131,28 +201,125 @@ impl<Cushion, UIContext> Batcher<Cushion, UIContext>
 where
     Cushion: Send,
 {
+    /// Replaces the transformer into the context of the UI
+    pub(super) fn with_ui<U>(self, cushion_to_ui: CushionToUIF<Cushion, U>) -> Batcher<Cushion, U> {
+        let Self {
+            filters,
+            sorters,
+            scorers,
+            generators,
+            sources,
+            live_sources,
+            actions,
+            fallback_actions,
+            cushion_to_ui: _,
+            dedup,
+            batch_size,
+            filter_and,
+            limit,
+            keep_open,
+            metrics,
+            state,
+        } = self;
+
+        Batcher {
+            filters,
+            sorters,
+            scorers,
+            generators,
+            sources,
+            live_sources,
+            actions,
+            fallback_actions,
+            cushion_to_ui,
+            dedup,
+            batch_size,
+            filter_and,
+            limit,
+            keep_open,
+            metrics,
+            state,
+        }
+    }
+
     /// Consumes (and destroys) the current instance, returning ownership of the `Cushion`.
     ///
     /// Call this function as the final step to retrieve the `Cushion`.
     #[inline(always)]
     pub fn compute_cushion(mut self, id: usize) -> Result<Cushion> {
-        ensure!(
-            self.state.items.len() > id,
-            "Failed to get Cushion, index is over the length. Maybe the ui is not using the usize obtained from Buffer"
-        );
+        self.state.items.remove(id).ok_or_eyre(NO_ITEM)
+    }
+
+    /// Borrows the `Cushion` of `id` (the usize in [`Buffer`]) without consuming the batcher,
+    /// e.g. for a preview of the item under the cursor.
+    #[inline]
+    pub fn cushion(&self, id: usize) -> Option<&Cushion> {
+        self.state.items.get(id)
+    }
+
+    /// Detaches the items from generators of the current input, see [`Batcher::swap_scratch`]
+    #[cfg(feature = "daemon")]
+    pub(crate) fn take_scratch(&mut self) -> items::Scratch<Cushion> {
+        self.state.items.take_scratch()
+    }
+
+    /// Swaps the items from generators with the detached ones, e.g. to resolve the ids of another input
+    #[cfg(feature = "daemon")]
+    pub(crate) fn swap_scratch(&mut self, scratch: &mut items::Scratch<Cushion>) {
+        self.state.items.swap_scratch(scratch);
+    }
+
+    /// Borrows the `Cushion` of the row at `index` of `buf`
+    #[inline]
+    pub fn cushion_at(&self, buf: &Buffer<(UIContext, usize)>, index: usize) -> Option<&Cushion> {
+        self.cushion(buf.as_slice().get(index)?.1)
+    }
+
+    /// Runs the actions of the launcher on the item of `id`, and keeps the launcher open.
+    ///
+    /// The actions are run again on the item returned by [`UI::run`](crate::ui::UI::run), if any.
+    pub fn act(&self, id: usize) -> Result<()> {
+        let cushion = self.cushion(id).ok_or_eyre(NO_ITEM)?;
+        let actions = self.actions.lock().unwrap_or_else(|e| e.into_inner());
+        for action in actions.iter() {
+            action.act(cushion)?;
+        }
 
-        Ok(self.state.items.swap_remove(id))
+        Ok(())
     }
 
     #[inline(always)]
-    fn create_sorter(&self) -> impl Fn(&usize, &usize) -> std::cmp::Ordering {
-        |lhs, rhs| {
+    fn create_sorter<'a>(
+        &'a self,
+        // 空でなければsorterごとの時間を足していく
+        times: &'a [Cell<Duration>],
+    ) -> impl Fn(&usize, &usize) -> std::cmp::Ordering + 'a {
+        move |lhs, rhs| {
             use std::cmp::Ordering;
 
+            if !self.scorers.is_empty() {
+                // higher score first
+                match self
+                    .state
+                    .items
+                    .score(*rhs)
+                    .total_cmp(&self.state.items.score(*lhs))
+                {
+                    Ordering::Equal => {}
+                    ord => return ord,
+                }
+            }
+
             let lhs = &self.state.items[*lhs];
             let rhs = &self.state.items[*rhs];
-            for si in &self.sorters {
-                match si.compare(lhs, rhs, &self.state.input) {
+            for (i, si) in self.sorters.iter().enumerate() {
+                let start = (!times.is_empty()).then(Instant::now);
+                let ord = si.compare(lhs, rhs, &self.state.input);
+                if let Some(start) = start {
+                    times[i].set(times[i].get() + start.elapsed());
+                }
+
+                match ord {
                     Ordering::Equal => {
                         continue;
                     }
@@ -180,12 +347,14 @@ where
         info!("Preparing");
         debug!("state on prepare {:?}", self.state);
 
-        if self.cushion_to_ui.is_none() {
-            panic!(
-                "{}",
-                eyre!(
-                    "Cushion to UIContext did not set. Did you set UI?(This error is probably not called because of the way Rust works!)"
-                )
+        let prepare_start = self.metrics.is_some().then(Instant::now);
+        if let Some(metrics) = &mut self.metrics {
+            metrics.reset(
+                self.sources.len(),
+                self.generators.len(),
+                self.filters.len(),
+                self.scorers.len(),
+                self.sorters.len(),
             );
         }
 
@@ -221,22 +390,28 @@ where
                 [self.state.gen_index..(self.state.gen_index + gen_count_to_run)]
                 .iter()
                 .map(|r#gen| async {
+                    let start = Instant::now();
                     let cushions = r#gen.generate(&self.state.input).await.into_iter();
                     // 最終結果で計算が終わったあとの長さにしか興味がないからRelaxedで問題ない
                     len.fetch_add(cushions.len(), Ordering::Relaxed);
-                    cushions
+                    (start.elapsed(), cushions)
                 });
 
-            let cushions_from_gen = futures::future::join_all(cushions_from_gen)
-                .await
-                .into_iter()
-                .flatten();
+            let cushions_from_gen = futures::future::join_all(cushions_from_gen).await;
+
+            if let Some(metrics) = &mut self.metrics {
+                for (i, (time, cushions)) in cushions_from_gen.iter().enumerate() {
+                    metrics.generators[self.state.gen_index + i] = Stage {
+                        time: *time,
+                        items_in: 0,
+                        items_out: cushions.len(),
+                    };
+                }
+            }
 
             v.reserve(len.load(Ordering::SeqCst));
-            for c in cushions_from_gen {
-                let index = self.state.items.len();
-                self.state.items.push(c);
-                v.push(index);
+            for c in cushions_from_gen.into_iter().flat_map(|(_, c)| c) {
+                v.push(self.state.items.push_scratch(c));
             }
 
             if batch_count < gen_count_to_run {
@@ -249,6 +424,37 @@ where
             self.state.gen_index += gen_count_to_run;
         }
 
+        // dedupで置き換えられたり、live sourceで消されたitemのid
+        let mut replaced = vec![];
+
+        if !self.live_sources.is_empty() {
+            let mut applied = Applied::default();
+            self.live_sources
+                .retain_mut(|live| live.apply(&mut self.state.items, &mut applied));
+
+            // 追加されたものは下のループでcacheから拾われる
+            for ci in applied.inserted {
+                self.state.items_from_sources_i.0.push(ci);
+            }
+            replaced.extend(applied.removed);
+
+            // 消されたidがcacheに溜まり続けないように、slotの数に比べて多くなったら詰める
+            let (cache, pos) = &mut self.state.items_from_sources_i;
+            if cache.len() > 2 * self.state.items.slots() + 64 {
+                let items = &self.state.items;
+                let before = pos.0;
+                let mut i = 0;
+                cache.as_mut().retain(|&ci| {
+                    let alive = items.contains(ci);
+                    if !alive && i < before {
+                        pos.0 -= 1;
+                    }
+                    i += 1;
+                    alive
+                });
+            }
+        }
+
         while batch_count != 0 {
             if let Some(ci) = self
                 .state
@@ -256,16 +462,32 @@ where
                 .0
                 .next(&mut self.state.items_from_sources_i.1)
             {
-                v.push(*ci);
+                if self.state.items.contains(*ci) {
+                    v.push(*ci);
+                }
             } else if self.state.source_index < self.sources.len() {
                 if let Some(cushion) = self.state.peeked_item.take() {
                     batch_count -= 1;
-                    self.state.items.push(cushion);
-                    v.push(self.state.items.len() - 1);
-                    self.state
-                        .items_from_sources_i
-                        .0
-                        .push(self.state.items.len() - 1);
+
+                    let outcome = if let Some(dedup) = &mut self.dedup {
+                        dedup.insert(&mut self.state.items, cushion, self.state.source_index)
+                    } else {
+                        DedupOutcome::Inserted(self.state.items.push(cushion))
+                    };
+
+                    match outcome {
+                        DedupOutcome::Inserted(ci) => {
+                            v.push(ci);
+                            self.state.items_from_sources_i.0.push(ci);
+                            // cacheから二重に拾わないように
+                            self.state.items_from_sources_i.1.0 += 1;
+                        }
+                        DedupOutcome::Replaced(ci) => {
+                            v.push(ci);
+                            replaced.push(ci);
+                        }
+                        DedupOutcome::Dropped => {}
+                    }
                 } else if !self.state.first_source {
                     self.state.source_index += 1;
                     if self.state.source_index == self.sources.len() {
@@ -278,35 +500,150 @@ where
 
                 // dbg!(&self.state);
 
-                self.state.peeked_item = self.sources[self.state.source_index].next().await;
+                self.state.peeked_item = loop {
+                    let start = self.metrics.is_some().then(Instant::now);
+                    let next = self.sources[self.state.source_index].next().await;
+                    if let (Some(metrics), Some(start)) = (&mut self.metrics, start) {
+                        let items = usize::from(matches!(next, Some(Ok(_))));
+                        metrics.sources[self.state.source_index].record(start, 0, items);
+                    }
+
+                    // エラーは次のmergeで返して、同じsourceの続きを読む
+                    match next {
+                        Some(Ok(cushion)) => break Some(cushion),
+                        Some(Err(e)) => self.state.errors.push(e),
+                        None => break None,
+                    }
+                };
             } else {
                 break;
             }
         }
 
-        let ctuf = self.cushion_to_ui.as_ref().unwrap();
+        if !replaced.is_empty() {
+            // 同じbatchの中で置き換えられたものが重複しないように
+            v.sort_unstable();
+            v.dedup();
+        }
 
-        let mut v: Vec<_> = v
-            .into_iter()
-            .filter(|ci| {
-                if self.filter_and {
-                    self.filters
-                        .iter()
-                        .all(|filter| filter.predicate(&self.state.items[*ci], &self.state.input))
-                } else {
-                    self.filters
-                        .iter()
-                        .any(|filter| filter.predicate(&self.state.items[*ci], &self.state.input))
+        let pulled = v.len();
+
+        let input = &self.state.input;
+        if self.filter_and {
+            // 前のfilterで落ちたものは次のfilterに渡さない
+            for (fi, filter) in self.filters.iter().enumerate() {
+                if v.is_empty() {
+                    break;
                 }
-            })
+
+                let start = self.metrics.is_some().then(Instant::now);
+                let ctxs: Vec<_> = v.iter().map(|ci| &self.state.items[*ci]).collect();
+                let mut keep = filter.predicate_batch(&ctxs, input).into_iter();
+                let items_in = v.len();
+                v.retain(|_| keep.next().unwrap_or(false));
+
+                if let (Some(metrics), Some(start)) = (&mut self.metrics, start) {
+                    metrics.filters[fi].record(start, items_in, v.len());
+                }
+            }
+        } else {
+            let mut passed = vec![false; v.len()];
+            for (fi, filter) in self.filters.iter().enumerate() {
+                let rest: Vec<_> = (0..v.len()).filter(|i| !passed[*i]).collect();
+                if rest.is_empty() {
+                    break;
+                }
+
+                let start = self.metrics.is_some().then(Instant::now);
+                let ctxs: Vec<_> = rest.iter().map(|i| &self.state.items[v[*i]]).collect();
+                let items_in = rest.len();
+                let mut items_out = 0;
+                for (i, keep) in rest.into_iter().zip(filter.predicate_batch(&ctxs, input)) {
+                    passed[i] = keep;
+                    items_out += usize::from(keep);
+                }
+
+                if let (Some(metrics), Some(start)) = (&mut self.metrics, start) {
+                    metrics.filters[fi].record(start, items_in, items_out);
+                }
+            }
+
+            let mut passed = passed.into_iter();
+            v.retain(|_| passed.next().unwrap_or(false));
+        }
+
+        if !self.scorers.is_empty() {
+            let mut totals = vec![0.0; v.len()];
+
+            let ctxs: Vec<_> = v.iter().map(|ci| &self.state.items[*ci]).collect();
+            for (si, (scorer, weight)) in self.scorers.iter().enumerate() {
+                let start = self.metrics.is_some().then(Instant::now);
+                for (total, score) in totals.iter_mut().zip(scorer.score_batch(&ctxs, input)) {
+                    *total += score * weight;
+                }
+
+                if let (Some(metrics), Some(start)) = (&mut self.metrics, start) {
+                    metrics.scorers[si].record(start, v.len(), v.len());
+                }
+            }
+
+            for (&ci, total) in v.iter().zip(totals) {
+                self.state.items.set_score(ci, total);
+            }
+        }
+
+        let truncated = self.limit != 0 && v.len() > self.limit;
+        let sorter_times = if self.metrics.is_some() {
+            vec![Cell::new(Duration::ZERO); self.sorters.len()]
+        } else {
+            vec![]
+        };
+        let sorted = v.len();
+        if !self.sorters.is_empty() && !v.is_empty() {
+            let ctxs: Vec<_> = v.iter().map(|ci| &self.state.items[*ci]).collect();
+            for (i, sorter) in self.sorters.iter().enumerate() {
+                let start = self.metrics.is_some().then(Instant::now);
+                sorter.prepare_batch(&ctxs, input);
+                if let Some(start) = start {
+                    sorter_times[i].set(start.elapsed());
+                }
+            }
+        }
+        {
+            let sorterf = self.create_sorter(&sorter_times);
+
+            if truncated {
+                // 上位limit件だけ残せばいいので全体をソートする前に部分ソートで削る
+                v.select_nth_unstable_by(self.limit - 1, &sorterf);
+                v.truncate(self.limit);
+            }
+
+            v.sort_by(&sorterf);
+        }
+        if let Some(metrics) = &mut self.metrics {
+            for (stage, time) in metrics.sorters.iter_mut().zip(sorter_times) {
+                *stage = Stage {
+                    time: time.into_inner(),
+                    items_in: sorted,
+                    items_out: v.len(),
+                };
+            }
+        }
+
+        let ctuf = &self.cushion_to_ui;
+
+        let v: Vec<_> = v
+            .into_iter()
             .map(|ci| (ctuf(&self.state.items[ci]), ci))
             .collect();
 
-        let sorterf = self.create_sorter();
+        self.state.truncated |= truncated;
 
-        v.sort_by(|(_, lhs), (_, rhs)| sorterf(lhs, rhs));
+        if let (Some(metrics), Some(start)) = (&mut self.metrics, prepare_start) {
+            metrics.prepare.record(start, pulled, v.len());
+        }
 
-        Prepared::new(v.into())
+        Prepared::new(v.into(), replaced)
     }
 
     /// Merges UI context data into the rendering buffer.
@@ -323,25 +660,58 @@ where
     /// Both the preparation and merge operations are relatively time-consuming. To minimize rendering delays,
     /// it is recommended that the preparation and rendering processes are executed concurrently (for example, in separate
     /// threads or processes), while the merge operation should be performed in a synchronized manner.
+    ///
+    /// An error from a try source is returned after the batch is merged into `buf`,
+    /// so the UI can keep calling `prepare` and `merge` to read the rest.
     pub fn merge(
         &mut self,
         buf: &mut Buffer<(UIContext, usize)>,
         from: Prepared<UIContext>,
+    ) -> Result<bool> {
+        self.merge_with_changes(buf, from, None)
+    }
+
+    /// [`Batcher::merge`] that also records what changed in `buf`
+    pub(crate) fn merge_with_changes(
+        &mut self,
+        buf: &mut Buffer<(UIContext, usize)>,
+        mut from: Prepared<UIContext>,
+        mut changes: Option<&mut Changes>,
     ) -> Result<bool> {
         debug!("state on merge: {:?}", self.state);
 
+        let merge_start = self.metrics.is_some().then(Instant::now);
+
         // sorterは順番に適用していくのと、逆にしてstd::Ordering::Equalが出たら次のやつを参照するっていうのが同義っぽいきがする
         // どっちにするかだけど、std::Ordering::Equalが出たら戻るほうが(ここでは逆にしたりしない)計算量が少なそう
 
+        let invalidated = std::mem::take(&mut from.invalidated);
         let v = from.into_inner().into_inner();
 
-        let sorterf = self.create_sorter();
+        if !invalidated.is_empty() {
+            let invalidated: std::collections::BTreeSet<_> = invalidated.into_iter().collect();
+            buf.as_mut().retain(|(_, ci)| {
+                let keep = !invalidated.contains(ci);
+                if let (false, Some(changes)) = (keep, changes.as_deref_mut()) {
+                    changes.removed.push(*ci);
+                }
+                keep
+            });
+        }
+
+        let items_in = buf.len() + v.len();
+        let truncated = {
+            let sorterf = self.create_sorter(&[]);
 
-        {
             let dst = buf.as_mut();
 
             let dst_owned = std::mem::take(dst);
-            let mut merged = Vec::with_capacity(dst_owned.len() + v.len());
+            let limit = if self.limit == 0 {
+                usize::MAX
+            } else {
+                self.limit
+            };
+            let mut merged = Vec::with_capacity(limit.min(dst_owned.len() + v.len()));
 
             let mut iter_dst = dst_owned.into_iter();
             let mut iter_src = v.into_iter();
@@ -349,50 +719,192 @@ where
             let mut next_dst = iter_dst.next();
             let mut next_src = iter_src.next();
 
-            while next_src.is_some() && next_dst.is_some() {
-                let a = next_dst.take().unwrap();
-                let b = next_src.take().unwrap();
-
+            while let (Some(a), Some(b)) = (&next_dst, &next_src)
+                && merged.len() < limit
+            {
+                // 選ばれなかった方は次の比較に残す
                 if sorterf(&a.1, &b.1) != std::cmp::Ordering::Greater {
-                    merged.push(a);
+                    merged.push(next_dst.take().unwrap());
                     next_dst = iter_dst.next();
                 } else {
-                    merged.push(b);
+                    if let Some(changes) = changes.as_deref_mut() {
+                        changes.inserted.push(merged.len());
+                    }
+                    merged.push(next_src.take().unwrap());
                     next_src = iter_src.next();
                 }
             }
 
-            if let Some(val) = next_dst {
-                merged.push(val);
-                merged.extend(iter_dst);
-            }
-            if let Some(val) = next_src {
-                merged.push(val);
-                merged.extend(iter_src);
+            // ループを抜けた時点でどちらかが空か、limitに達している
+            let mut rest_dst = next_dst.into_iter().chain(iter_dst).peekable();
+            let mut rest_src = next_src.into_iter().chain(iter_src);
+            merged.extend(rest_dst.by_ref().take(limit - merged.len()));
+            let start = merged.len();
+            merged.extend(rest_src.by_ref().take(limit - merged.len()));
+
+            let truncated = rest_dst.peek().is_some() || rest_src.next().is_some();
+            if let Some(changes) = changes {
+                changes.inserted.extend(start..merged.len());
+                // limitで押し出されたもの
+                changes.removed.extend(rest_dst.map(|(_, ci)| ci));
             }
 
             *dst = merged;
+
+            truncated
+        };
+        self.state.truncated |= truncated;
+
+        if let (Some(metrics), Some(start)) = (&mut self.metrics, merge_start) {
+            metrics.merge = Stage::default();
+            metrics.merge.record(start, items_in, buf.len());
+        }
+
+        // batchはmerge済みなのでitemは失われない。残りのerrorは次のmergeで返す
+        if !self.state.errors.is_empty() {
+            return Err(self.state.errors.remove(0));
         }
 
-        Ok(self.state.peeked_item.is_some())
+        // batchの終わりとsourceの終わりが重なったときはpeeked_itemがNoneでも次のsourceが残っている
+        Ok(self.state.peeked_item.is_some()
+            || self.state.source_index + 1 < self.sources.len()
+            || self.state.items_from_sources_i.1.0 < self.state.items_from_sources_i.0.len())
+    }
+
+    /// Returns true while some live sources are open.
+    /// Their events are applied on [`Batcher::prepare`], so the UI should keep calling `prepare` and `merge`
+    /// (e.g. periodically) even after [`Batcher::merge`] returned false.
+    pub fn is_live(&self) -> bool {
+        !self.live_sources.is_empty()
+    }
+
+    /// The current input
+    pub fn query(&self) -> &str {
+        &self.state.input
     }
 
     /// Accepts user input, resets the internal state, and initiates processing of a new batch.
     pub fn input(&mut self, buf: &mut Buffer<(UIContext, usize)>, input: &str) {
         self.state.input = input.into();
         self.state.gen_index = 0;
+        // generatorのitemは前のinputのものなので捨てる
+        self.state.items.clear_scratch();
+        self.state.truncated = false;
         buf.reset();
 
         // Positionだけリセット。元(Positionを分けるまえ)のコードにはバグがあって(多分)全部払い出したあとにinputすると変になってた
         self.state.items_from_sources_i.1.reset();
     }
 
+    /// Whether the launcher stays open after an action.
+    /// If true, the UI should call [`Batcher::act_and_reset`] on a selection instead of returning the item.
+    /// See [`Launcher::keep_open`](crate::launcher::Launcher::keep_open).
+    pub fn keep_open(&self) -> bool {
+        self.keep_open
+    }
+
+    /// Runs the actions on the item of `id` by [`Action::act_in_loop`], then starts over with an empty input
+    /// unless an action returned [`Flow::Close`]. The items from the sources are kept.
+    ///
+    /// On [`Flow::Continue`], the UI should clear its input field. On [`Flow::Close`], it should return `Ok(None)`.
+    pub fn act_and_reset(
+        &mut self,
+        buf: &mut Buffer<(UIContext, usize)>,
+        id: usize,
+    ) -> Result<Flow> {
+        let cushion = self.state.items.get(id).ok_or_eyre(NO_ITEM)?;
+
+        let mut flow = Flow::Continue;
+        {
+            let actions = self.actions.lock().unwrap_or_else(|e| e.into_inner());
+            for action in actions.iter() {
+                if action.act_in_loop(cushion)? == Flow::Close {
+                    flow = Flow::Close;
+                }
+            }
+        }
+
+        if flow == Flow::Continue {
+            self.input(buf, "");
+        }
+
+        Ok(flow)
+    }
+
+    /// Same as [`Batcher::act_and_reset`], but runs the fallback actions on the current input
+    pub fn act_query_and_reset(&mut self, buf: &mut Buffer<(UIContext, usize)>) -> Result<Flow> {
+        let mut flow = Flow::Continue;
+        {
+            let actions = self
+                .fallback_actions
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            for action in actions.iter() {
+                if action.act_in_loop(&self.state.input)? == Flow::Close {
+                    flow = Flow::Close;
+                }
+            }
+        }
+
+        if flow == Flow::Continue {
+            self.input(buf, "");
+        }
+
+        Ok(flow)
+    }
+
+    /// Metrics of the last prepare and merge, or None if they are not collected.
+    /// See [`Launcher::collect_metrics`](crate::launcher::Launcher::collect_metrics).
+    pub fn metrics(&self) -> Option<&Metrics> {
+        self.metrics.as_ref()
+    }
+
+    /// The maximum number of items kept in the rendering buffer. 0 means no limit.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Returns true if some items were dropped from the rendering buffer because of the limit
+    /// since the last [`Batcher::input`]. In that case [`Batcher::extend_limit`] can be used to get more rows.
+    pub fn is_truncated(&self) -> bool {
+        self.state.truncated
+    }
+
+    /// Raises the limit by `additional` and starts the current input over again so that
+    /// the buffer is filled up to the new limit (e.g. when the user scrolls to the bottom).
+    ///
+    /// Does nothing if there is no limit.
+    pub fn extend_limit(&mut self, buf: &mut Buffer<(UIContext, usize)>, additional: usize) {
+        if self.limit == 0 {
+            return;
+        }
+
+        self.limit = self.limit.saturating_add(additional);
+        let input = std::mem::take(&mut self.state.input);
+        self.input(buf, &input);
+    }
+
     // そういえばSourceだけもともとBoxを求めてる(まあいいや)
     /// Add a source to `self`, builder
-    pub(super) fn add_raw_source(&mut self, source: Source<Cushion>) {
+    pub(super) fn add_raw_source(&mut self, source: Source<Cushion>)
+    where
+        Cushion: 'static,
+    {
+        self.sources.push(Box::pin(source.map(Ok)));
+    }
+
+    pub(super) fn add_raw_try_source(&mut self, source: Source<Result<Cushion>>) {
         self.sources.push(source);
     }
 
+    pub(super) fn add_raw_live_source<Id>(&mut self, source: LiveSource<Id, Cushion>)
+    where
+        Id: Ord + Send + 'static,
+        Cushion: 'static,
+    {
+        self.live_sources.push(Box::new(LiveState::new(source)));
+    }
+
     pub(super) fn add_raw_filter<FilterT>(&mut self, filter: FilterT)
     where
         FilterT: Filter<Context = Cushion> + 'static,
@@ -407,6 +919,13 @@ where
         self.sorters.push(Box::new(sorter));
     }
 
+    pub(super) fn add_raw_scorer<ScorerT>(&mut self, scorer: ScorerT, weight: f64)
+    where
+        ScorerT: Scorer<Context = Cushion> + 'static,
+    {
+        self.scorers.push((Box::new(scorer), weight));
+    }
+
     pub(super) fn add_raw_generator<GenT>(&mut self, generator: GenT)
     where
         GenT: Generator<Item = Cushion> + Sync + Send + 'static,
@@ -435,7 +954,7 @@ mod tests {
         let mut batcher: Batcher<i32, ()> = Batcher::default();
 
         batcher.add_raw_filter(crate::filter::ClosureFilter::new(|&x: &i32, input| {
-            x == 0i32 && input == ""
+            x == 0i32 && input.is_empty()
         }));
 
         assert_eq!(batcher.filters.len(), 1);
@@ -469,15 +988,267 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn add_scorer() -> Result<(), Box<dyn std::error::Error>> {
+        let mut batcher: Batcher<i32, ()> = Batcher::default();
+
+        batcher.add_raw_scorer(
+            crate::scorer::ClosureScorer::new(|&x: &i32, _| x as f64),
+            1.0,
+        );
+
+        assert_eq!(batcher.scorers.len(), 1);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_prepare() -> Result<(), Box<dyn std::error::Error>> {
         let mut batcher: Batcher<i32, ()> = Batcher::default();
-        batcher.cushion_to_ui = Some(Box::new(|_: &i32| ()));
 
         batcher.add_raw_source(Box::pin(tokio_stream::iter(vec![1, 2])));
 
         let buf = batcher.prepare().await;
-        assert_eq!(buf.0.len(), 2);
+        assert_eq!(buf.buf.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_metrics() -> Result<(), Box<dyn std::error::Error>> {
+        let mut batcher: Batcher<i32, i32> = Batcher {
+            metrics: Some(Metrics::default()),
+            batch_size: 6,
+            ..Default::default()
+        }
+        .with_ui(Box::new(|&x: &i32| x));
+        batcher.add_raw_source(Box::pin(tokio_stream::iter(0..10)));
+        batcher.add_raw_filter(crate::filter::ClosureFilter::new(|x: &i32, _| x % 2 == 0));
+        batcher.add_raw_sorter(crate::sorter::ClosureSorter::new(|lhs: &i32, rhs, _| {
+            rhs.cmp(lhs)
+        }));
+
+        let mut buf = Buffer::default();
+        let from = batcher.prepare().await;
+        batcher.merge(&mut buf, from)?;
+
+        let metrics = batcher.metrics().unwrap();
+        // 次のbatchの最初の1件も先読みされている
+        assert_eq!(metrics.sources[0].items_out, 7);
+        assert_eq!(metrics.prepare.items_in, 6);
+        assert_eq!(
+            (metrics.filters[0].items_in, metrics.filters[0].items_out),
+            (6, 3)
+        );
+        assert_eq!(metrics.sorters[0].items_in, 3);
+        assert_eq!((metrics.merge.items_in, metrics.merge.items_out), (3, 3));
+
+        let from = batcher.prepare().await;
+        batcher.merge(&mut buf, from)?;
+
+        let metrics = batcher.metrics().unwrap();
+        assert_eq!(metrics.filters[0].items_in, 4);
+        assert_eq!((metrics.merge.items_in, metrics.merge.items_out), (5, 5));
+
+        assert!(Batcher::<i32, ()>::default().metrics().is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_generator_items() -> Result<(), Box<dyn std::error::Error>> {
+        let mut batcher: Batcher<String, String> =
+            Batcher::default().with_ui(Box::new(|x: &String| x.clone()));
+        batcher.add_raw_source(Box::pin(tokio_stream::iter(vec!["a".to_string()])));
+        batcher.add_raw_generator(crate::generator::ClosureGenerator::new(|input: &str| {
+            vec![format!("={input}")]
+        }));
+
+        let mut buf = Buffer::default();
+        let mut ids = vec![];
+        for input in ["1", "12", "123"] {
+            batcher.input(&mut buf, input);
+            let from = batcher.prepare().await;
+            batcher.merge(&mut buf, from)?;
+            let gen_id = buf.as_slice().iter().map(|(_, id)| *id).find(|id| *id != 0);
+            assert_eq!(buf.len(), 2);
+            ids.push(gen_id.unwrap());
+        }
+
+        // 前のinputのgeneratorのitemは残らない
+        assert_eq!(batcher.state.items.len(), 2);
+        // sourceのitem(id 0)はそのままで、generatorのitemの古いidは使われない
+        assert_eq!(batcher.state.items[0], "a");
+        assert!(batcher.state.items.get(ids[0]).is_none());
+        assert_eq!(batcher.state.items[ids[2]], "=123");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_live_source() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::source::SourceEvent;
+
+        let mut batcher: Batcher<i32, i32> = Batcher::default().with_ui(Box::new(|&x: &i32| x));
+        batcher.add_raw_sorter(crate::sorter::ClosureSorter::new(|lhs: &i32, rhs, _| {
+            lhs.cmp(rhs)
+        }));
+
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        batcher.add_raw_live_source(Box::pin(
+            tokio_stream::wrappers::UnboundedReceiverStream::new(rx),
+        ));
+
+        let mut buf = Buffer::default();
+        let shown = |buf: &mut Buffer<(i32, usize)>| {
+            buf.as_mut().iter().map(|(x, _)| *x).collect::<Vec<_>>()
+        };
+
+        tx.send(SourceEvent::Insert("a", 10))?;
+        tx.send(SourceEvent::Insert("b", 20))?;
+        let from = batcher.prepare().await;
+        batcher.merge(&mut buf, from)?;
+        assert_eq!(shown(&mut buf), [10, 20]);
+
+        tx.send(SourceEvent::Update("a", 15))?;
+        tx.send(SourceEvent::Remove("b"))?;
+        tx.send(SourceEvent::Insert("c", 5))?;
+        let from = batcher.prepare().await;
+        batcher.merge(&mut buf, from)?;
+        assert_eq!(shown(&mut buf), [5, 15]);
+
+        batcher.input(&mut buf, "");
+        let from = batcher.prepare().await;
+        batcher.merge(&mut buf, from)?;
+        assert_eq!(shown(&mut buf), [5, 15]);
+        assert!(batcher.is_live());
+
+        drop(tx);
+        let from = batcher.prepare().await;
+        batcher.merge(&mut buf, from)?;
+        assert!(!batcher.is_live());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_live_source_churn() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::source::SourceEvent;
+
+        let mut batcher: Batcher<i32, i32> = Batcher::default().with_ui(Box::new(|&x: &i32| x));
+        batcher.add_raw_sorter(crate::sorter::ClosureSorter::new(|lhs: &i32, rhs, _| {
+            lhs.cmp(rhs)
+        }));
+
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        batcher.add_raw_live_source(Box::pin(
+            tokio_stream::wrappers::UnboundedReceiverStream::new(rx),
+        ));
+
+        let mut buf = Buffer::default();
+        for round in 0..1000 {
+            for key in 0..10 {
+                tx.send(SourceEvent::Update(key, round * 10 + key))?;
+            }
+            // 一部は消して次のroundで入れ直す
+            tx.send(SourceEvent::Remove(round % 10))?;
+            // UIと同じように間でyieldしないとtokioのbudgetが尽きてPendingになる
+            tokio::task::yield_now().await;
+
+            let from = batcher.prepare().await;
+            batcher.merge(&mut buf, from)?;
+            if round % 100 == 0 {
+                batcher.input(&mut buf, "");
+                let from = batcher.prepare().await;
+                batcher.merge(&mut buf, from)?;
+            }
+        }
+
+        let expected: Vec<_> = (0..10)
+            .filter(|&key| key != 9)
+            .map(|key| 9990 + key)
+            .collect();
+        assert_eq!(
+            buf.as_mut().iter().map(|(x, _)| *x).collect::<Vec<_>>(),
+            expected
+        );
+        // 更新のたびに増えない
+        assert!(batcher.state.items.slots() <= 10);
+        assert!(batcher.state.items_from_sources_i.0.len() <= 2 * 10 + 64 + 11);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_merge_changes() -> Result<(), Box<dyn std::error::Error>> {
+        let mut batcher: Batcher<i32, i32> = Batcher {
+            limit: 3,
+            batch_size: 2,
+            ..Default::default()
+        }
+        .with_ui(Box::new(|&x: &i32| x));
+        batcher.add_raw_sorter(crate::sorter::ClosureSorter::new(|lhs: &i32, rhs, _| {
+            lhs.cmp(rhs)
+        }));
+        batcher.add_raw_source(Box::pin(tokio_stream::iter(vec![5, 3, 4, 1])));
+
+        let mut buf = Buffer::default();
+        let mut changes = Changes::default();
+        let from = batcher.prepare().await;
+        batcher.merge_with_changes(&mut buf, from, Some(&mut changes))?;
+        assert!(changes.removed.is_empty());
+        assert_eq!(changes.inserted, [0, 1]);
+
+        let ids: Vec<_> = buf.as_slice().iter().map(|(_, ci)| *ci).collect();
+        let mut changes = Changes::default();
+        let from = batcher.prepare().await;
+        batcher.merge_with_changes(&mut buf, from, Some(&mut changes))?;
+        // 1と4が入って5が押し出される
+        assert_eq!(
+            buf.as_slice().iter().map(|(x, _)| *x).collect::<Vec<_>>(),
+            [1, 3, 4]
+        );
+        assert_eq!(changes.removed, [ids[1]]);
+        assert_eq!(changes.inserted, [0, 2]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_extend_limit() -> Result<(), Box<dyn std::error::Error>> {
+        let mut batcher: Batcher<i32, i32> = Batcher {
+            limit: 3,
+            batch_size: 4,
+            ..Default::default()
+        }
+        .with_ui(Box::new(|&x: &i32| x));
+        batcher.add_raw_sorter(crate::sorter::ClosureSorter::new(|lhs: &i32, rhs, _| {
+            lhs.cmp(rhs)
+        }));
+        batcher.add_raw_source(Box::pin(tokio_stream::iter(vec![5, 1, 4, 2, 3, 0])));
+
+        let mut buf = Buffer::default();
+        let mut more = true;
+        while more {
+            let from = batcher.prepare().await;
+            more = batcher.merge(&mut buf, from)?;
+        }
+
+        assert_eq!(
+            buf.as_mut().iter().map(|(x, _)| *x).collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+        assert!(batcher.is_truncated());
+
+        batcher.extend_limit(&mut buf, 10);
+        let from = batcher.prepare().await;
+        batcher.merge(&mut buf, from)?;
+
+        assert_eq!(
+            buf.as_mut().iter().map(|(x, _)| *x).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4, 5]
+        );
+        assert!(!batcher.is_truncated());
+
         Ok(())
     }
 }
@@ -0,0 +1,311 @@
+/// Ids at or above this are of the items from generators
+const SCRATCH: usize = usize::MAX / 2 + 1;
+
+/// The low bits of a source id are the slot, and the rest (below [`SCRATCH`]) is the generation of the slot
+const SLOT_BITS: u32 = if usize::BITS >= 64 { 40 } else { 24 };
+const SLOT_MASK: usize = (1 << SLOT_BITS) - 1;
+/// A slot whose generation reached this is not reused any more
+const MAX_GENERATION: usize = (SCRATCH - 1) >> SLOT_BITS;
+
+/// Storage of the items. The id of an item never changes, even after other items are removed.
+///
+/// Items from sources live until they are removed (e.g. by a live source), while items from generators
+/// ("scratch" items) only live until the input changes. They have separate id spaces, so dropping
+/// the scratch items doesn't shift the ids of the source items.
+///
+/// The slots of removed source items are reused, but with a new generation in the id,
+/// so an old id can't point to another item.
+pub(crate) struct Items<Cushion> {
+    vec: Vec<Option<Cushion>>,
+    /// The current generation of each slot of `vec`
+    generations: Vec<usize>,
+    /// Empty slots of `vec` to reuse
+    free: Vec<usize>,
+    /// Weighted total score of the items, indexed the same way as `vec`.
+    /// Only the items that passed the filters in the current input are up to date.
+    scores: Vec<f64>,
+
+    scratch: Vec<Option<Cushion>>,
+    scratch_scores: Vec<f64>,
+    /// The id of `scratch[0]`. It only increases.
+    scratch_start: usize,
+}
+
+impl<Cushion> Default for Items<Cushion> {
+    fn default() -> Self {
+        Self {
+            vec: vec![],
+            generations: vec![],
+            free: vec![],
+            scores: vec![],
+            scratch: vec![],
+            scratch_scores: vec![],
+            scratch_start: SCRATCH,
+        }
+    }
+}
+
+/// Items from generators detached by [`Items::take_scratch`]
+#[cfg(feature = "daemon")]
+pub(crate) struct Scratch<Cushion> {
+    items: Vec<Option<Cushion>>,
+    scores: Vec<f64>,
+    start: usize,
+}
+
+#[cfg(feature = "daemon")]
+impl<Cushion> Default for Scratch<Cushion> {
+    fn default() -> Self {
+        Self {
+            items: vec![],
+            scores: vec![],
+            start: SCRATCH,
+        }
+    }
+}
+
+enum Slot {
+    /// A slot of `vec` with the generation of the id
+    Source(usize, usize),
+    Scratch(usize),
+    /// A scratch id of an old input
+    Stale,
+}
+
+impl<Cushion> Items<Cushion> {
+    #[inline]
+    fn slot(&self, id: usize) -> Slot {
+        if id < SCRATCH {
+            Slot::Source(id & SLOT_MASK, id >> SLOT_BITS)
+        } else if id >= self.scratch_start {
+            Slot::Scratch(id - self.scratch_start)
+        } else {
+            Slot::Stale
+        }
+    }
+
+    /// The index of `vec` if the id is of the current generation
+    #[inline]
+    fn source_slot(&self, id: usize) -> Option<usize> {
+        match self.slot(id) {
+            Slot::Source(i, generation) => {
+                (self.generations.get(i) == Some(&generation)).then_some(i)
+            }
+            _ => None,
+        }
+    }
+
+    /// The number of the items alive
+    pub(crate) fn len(&self) -> usize {
+        self.vec.iter().chain(&self.scratch).flatten().count()
+    }
+
+    /// The number of the slots for the source items, including the empty ones
+    pub(crate) fn slots(&self) -> usize {
+        self.vec.len()
+    }
+
+    #[inline]
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.vec.reserve(additional);
+    }
+
+    /// Pushes `cushion` from a source and returns its id
+    #[inline]
+    pub(crate) fn push(&mut self, cushion: Cushion) -> usize {
+        if let Some(i) = self.free.pop() {
+            self.vec[i] = Some(cushion);
+            return (self.generations[i] << SLOT_BITS) | i;
+        }
+
+        debug_assert!(self.vec.len() <= SLOT_MASK, "too many items");
+        self.vec.push(Some(cushion));
+        self.generations.push(0);
+        self.vec.len() - 1
+    }
+
+    /// Replaces the source item of `id` in its slot and returns the new id of it.
+    /// The old id no longer points to the item. Returns None if the item doesn't exist.
+    pub(crate) fn replace(&mut self, id: usize, cushion: Cushion) -> Option<usize> {
+        let i = self.source_slot(id)?;
+        if self.generations[i] == MAX_GENERATION {
+            // これ以上世代を進められないので別のslotに移す
+            self.remove(id);
+            return Some(self.push(cushion));
+        }
+
+        self.generations[i] += 1;
+        self.vec[i] = Some(cushion);
+        Some((self.generations[i] << SLOT_BITS) | i)
+    }
+
+    /// Pushes `cushion` from a generator and returns its id. It is dropped by [`Items::clear_scratch`].
+    #[inline]
+    pub(crate) fn push_scratch(&mut self, cushion: Cushion) -> usize {
+        self.scratch.push(Some(cushion));
+        self.scratch_start + self.scratch.len() - 1
+    }
+
+    /// Drops the items from generators. Their ids will not be reused.
+    pub(crate) fn clear_scratch(&mut self) {
+        self.scratch_start += self.scratch.len();
+        self.scratch = vec![];
+        self.scratch_scores = vec![];
+    }
+
+    /// Detaches the items from generators, like [`Items::clear_scratch`] but keeping them in the returned [`Scratch`]
+    #[cfg(feature = "daemon")]
+    pub(crate) fn take_scratch(&mut self) -> Scratch<Cushion> {
+        let scratch = Scratch {
+            items: std::mem::take(&mut self.scratch),
+            scores: std::mem::take(&mut self.scratch_scores),
+            start: self.scratch_start,
+        };
+        self.scratch_start += scratch.items.len();
+        scratch
+    }
+
+    /// Swaps the items from generators with the detached ones, so that their ids point to them again
+    #[cfg(feature = "daemon")]
+    pub(crate) fn swap_scratch(&mut self, scratch: &mut Scratch<Cushion>) {
+        std::mem::swap(&mut self.scratch, &mut scratch.items);
+        std::mem::swap(&mut self.scratch_scores, &mut scratch.scores);
+        std::mem::swap(&mut self.scratch_start, &mut scratch.start);
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, id: usize) -> Option<&Cushion> {
+        match self.slot(id) {
+            Slot::Source(..) => self.vec[self.source_slot(id)?].as_ref(),
+            Slot::Scratch(i) => self.scratch.get(i)?.as_ref(),
+            Slot::Stale => None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn contains(&self, id: usize) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Removes the item. The slot may be reused, but not the id.
+    #[inline]
+    pub(crate) fn remove(&mut self, id: usize) -> Option<Cushion> {
+        match self.slot(id) {
+            Slot::Source(..) => {
+                let i = self.source_slot(id)?;
+                let cushion = self.vec[i].take()?;
+                if self.generations[i] < MAX_GENERATION {
+                    self.generations[i] += 1;
+                    self.free.push(i);
+                }
+                Some(cushion)
+            }
+            Slot::Scratch(i) => self.scratch.get_mut(i)?.take(),
+            Slot::Stale => None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn score(&self, id: usize) -> f64 {
+        match self.slot(id) {
+            Slot::Source(..) => self.source_slot(id).and_then(|i| self.scores.get(i)),
+            Slot::Scratch(i) => self.scratch_scores.get(i),
+            Slot::Stale => None,
+        }
+        .copied()
+        .unwrap_or(0.0)
+    }
+
+    pub(crate) fn set_score(&mut self, id: usize, score: f64) {
+        let (scores, i, len) = match self.slot(id) {
+            Slot::Source(..) => match self.source_slot(id) {
+                Some(i) => (&mut self.scores, i, self.vec.len()),
+                None => return,
+            },
+            Slot::Scratch(i) => (&mut self.scratch_scores, i, self.scratch.len()),
+            Slot::Stale => return,
+        };
+
+        if scores.len() <= i {
+            scores.resize(len.max(i + 1), 0.0);
+        }
+        scores[i] = score;
+    }
+}
+
+impl<Cushion> std::ops::Index<usize> for Items<Cushion> {
+    type Output = Cushion;
+
+    /// Panics if the item has been removed
+    #[inline]
+    fn index(&self, id: usize) -> &Self::Output {
+        self.get(id)
+            .expect("the item has been removed from the batcher")
+    }
+}
+
+impl<Cushion> std::ops::IndexMut<usize> for Items<Cushion> {
+    #[inline]
+    fn index_mut(&mut self, id: usize) -> &mut Self::Output {
+        match self.slot(id) {
+            Slot::Source(..) => self.source_slot(id).and_then(|i| self.vec[i].as_mut()),
+            Slot::Scratch(i) => self.scratch[i].as_mut(),
+            Slot::Stale => None,
+        }
+        .expect("the item has been removed from the batcher")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scratch() {
+        let mut items = Items::default();
+        let a = items.push("a");
+        let b = items.push_scratch("b");
+        assert_eq!((items[a], items[b]), ("a", "b"));
+
+        items.set_score(b, 1.0);
+        assert_eq!(items.score(b), 1.0);
+        assert_eq!(items.score(a), 0.0);
+
+        items.clear_scratch();
+        let c = items.push_scratch("c");
+        let d = items.push("d");
+
+        assert_ne!(b, c);
+        assert_eq!(items.get(b), None);
+        assert_eq!(items.score(b), 0.0);
+        assert_eq!((items[a], items[c], items[d]), ("a", "c", "d"));
+        assert_eq!(d, a + 1);
+        assert_eq!(items.len(), 3);
+    }
+
+    #[test]
+    fn reuse() {
+        let mut items = Items::default();
+        let a = items.push("a");
+        let b = items.push("b");
+
+        assert_eq!(items.remove(a), Some("a"));
+        assert_eq!(items.remove(a), None);
+        let c = items.push("c");
+        // slotは再利用するけど古いidからは見えない
+        assert_ne!(a, c);
+        assert_eq!(items.get(a), None);
+        assert_eq!(items[c], "c");
+
+        let b2 = items.replace(b, "b2").unwrap();
+        assert_ne!(b, b2);
+        assert_eq!(items.get(b), None);
+        assert_eq!(items.replace(b, "x"), None);
+        assert_eq!(items[b2], "b2");
+
+        items.set_score(c, 1.0);
+        items.set_score(a, 2.0);
+        assert_eq!((items.score(a), items.score(c)), (0.0, 1.0));
+        assert_eq!(items.slots(), 2);
+    }
+}
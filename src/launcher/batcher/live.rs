@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+
+use futures::FutureExt as _;
+use tokio_stream::StreamExt as _;
+
+use super::items::Items;
+use crate::source::{LiveSource, SourceEvent};
+
+/// Changes made to the items by live sources
+#[derive(Debug, Default)]
+pub(crate) struct Applied {
+    /// ids of the new items, they have not been shown in the current input yet
+    pub(crate) inserted: Vec<usize>,
+    /// ids of removed (or replaced) items
+    pub(crate) removed: Vec<usize>,
+}
+
+pub(crate) trait Live<Cushion>: Send {
+    /// Applies all the events that are ready without waiting.
+    /// Returns false when the source is closed.
+    fn apply(&mut self, items: &mut Items<Cushion>, applied: &mut Applied) -> bool;
+}
+
+pub(crate) struct LiveState<Id, Cushion> {
+    source: LiveSource<Id, Cushion>,
+
+    /// id of the source -> id of items
+    ids: BTreeMap<Id, usize>,
+}
+
+impl<Id, Cushion> LiveState<Id, Cushion> {
+    pub(crate) fn new(source: LiveSource<Id, Cushion>) -> Self {
+        Self {
+            source,
+            ids: BTreeMap::new(),
+        }
+    }
+}
+
+impl<Id, Cushion> Live<Cushion> for LiveState<Id, Cushion>
+where
+    Id: Ord + Send,
+    Cushion: Send,
+{
+    fn apply(&mut self, items: &mut Items<Cushion>, applied: &mut Applied) -> bool {
+        loop {
+            // Pendingならそこで止める
+            let event = match self.source.next().now_or_never() {
+                Some(Some(event)) => event,
+                Some(None) => return false,
+                None => return true,
+            };
+
+            match event {
+                SourceEvent::Insert(id, cushion) | SourceEvent::Update(id, cushion) => {
+                    // 置き換えは同じslotで新しいidにする(bufferにある古いidのものはmergeで消える)
+                    let ci = match self.ids.get(&id) {
+                        Some(&old) if items.contains(old) => items.replace(old, cushion).unwrap(),
+                        _ => items.push(cushion),
+                    };
+                    applied.inserted.push(ci);
+
+                    if let Some(old) = self.ids.insert(id, ci) {
+                        applied.removed.push(old);
+                    }
+                }
+                SourceEvent::Remove(id) => {
+                    if let Some(old) = self.ids.remove(&id) {
+                        items.remove(old);
+                        applied.removed.push(old);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Time spent in each stage of a batch, e.g. for a debug overlay of the UI.
+//!
+//! Metrics are collected only when enabled by [`Launcher::collect_metrics`](crate::launcher::Launcher::collect_metrics),
+//! and read by [`Batcher::metrics`](super::Batcher::metrics).
+
+use std::time::{Duration, Instant};
+
+/// The time and the number of items of a stage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stage {
+    pub time: Duration,
+    /// The number of items given to the stage
+    pub items_in: usize,
+    /// The number of items that came out of the stage (e.g. passed a filter)
+    pub items_out: usize,
+}
+
+impl Stage {
+    #[inline]
+    pub(super) fn record(&mut self, start: Instant, items_in: usize, items_out: usize) {
+        self.time += start.elapsed();
+        self.items_in += items_in;
+        self.items_out += items_out;
+    }
+}
+
+/// Metrics of the last [`Batcher::prepare`](super::Batcher::prepare) and [`Batcher::merge`](super::Batcher::merge).
+///
+/// The stages of each kind of extension are in the order they were added.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Time spent waiting for the items. Only `items_out` is counted.
+    pub sources: Vec<Stage>,
+    /// Generators run concurrently, so their times overlap. Only `items_out` is counted.
+    pub generators: Vec<Stage>,
+    pub filters: Vec<Stage>,
+    pub scorers: Vec<Stage>,
+    /// Time spent in the comparisons of each sorter while sorting the batch.
+    /// `items_in` and `items_out` are the number of items sorted.
+    pub sorters: Vec<Stage>,
+    /// The whole prepare. `items_in` is the number of items taken from the sources, the generators and the cache,
+    /// and `items_out` is the number of the prepared items.
+    pub prepare: Stage,
+    /// `items_in` is the number of the prepared items and the items already in the buffer,
+    /// and `items_out` is the length of the buffer after the merge.
+    pub merge: Stage,
+}
+
+impl Metrics {
+    /// Clears the metrics of the previous prepare
+    pub(super) fn reset(
+        &mut self,
+        sources: usize,
+        generators: usize,
+        filters: usize,
+        scorers: usize,
+        sorters: usize,
+    ) {
+        let merge = self.merge;
+        *self = Self {
+            sources: vec![Stage::default(); sources],
+            generators: vec![Stage::default(); generators],
+            filters: vec![Stage::default(); filters],
+            scorers: vec![Stage::default(); scorers],
+            sorters: vec![Stage::default(); sorters],
+            prepare: Stage::default(),
+            merge,
+        };
+    }
+}
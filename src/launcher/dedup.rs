@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+
+use crate::launcher::batcher::items::Items;
+
+type MergeF<Cushion> = Box<dyn Fn(&mut Cushion, Cushion) + Send>;
+
+/// Decides which item is kept when two items from sources have the same key.
+/// See [`Launcher::dedup`](crate::launcher::Launcher::dedup).
+pub enum DedupPolicy<Cushion> {
+    /// Keeps the item that was seen first and drops the others.
+    FirstSeen,
+    /// Keeps the item from the source with the highest priority.
+    ///
+    /// The sources are identified by the order in which they were added (`add_source` and
+    /// `add_raw_source`, starting at 0) and listed from the highest priority.
+    /// Sources not in the list have the lowest priority, and items from sources of the same priority
+    /// are handled as [`DedupPolicy::FirstSeen`].
+    SourcePriority(Vec<usize>),
+    /// Merges the later item (the second argument) into the item that was seen first.
+    Merge(MergeF<Cushion>),
+}
+
+pub(crate) enum DedupOutcome {
+    /// The item was new and pushed with this id
+    Inserted(usize),
+    /// The item was a duplicate and dropped
+    Dropped,
+    /// The item was a duplicate and the stored item of this id was replaced (or merged)
+    Replaced(usize),
+}
+
+pub(crate) trait Dedup<Cushion>: Send {
+    /// Pushes `cushion` (sourced from `source`) to `items` unless it is a duplicate
+    fn insert(
+        &mut self,
+        items: &mut Items<Cushion>,
+        cushion: Cushion,
+        source: usize,
+    ) -> DedupOutcome;
+}
+
+pub(crate) struct Deduplicator<Cushion, K, F>
+where
+    F: Fn(&Cushion) -> K,
+{
+    key: F,
+    policy: DedupPolicy<Cushion>,
+
+    /// key -> (id of items, index of the source)
+    seen: BTreeMap<K, (usize, usize)>,
+}
+
+impl<Cushion, K, F> Deduplicator<Cushion, K, F>
+where
+    F: Fn(&Cushion) -> K,
+{
+    pub(crate) fn new(key: F, policy: DedupPolicy<Cushion>) -> Self {
+        Self {
+            key,
+            policy,
+            seen: BTreeMap::new(),
+        }
+    }
+}
+
+impl<Cushion, K, F> Dedup<Cushion> for Deduplicator<Cushion, K, F>
+where
+    F: Fn(&Cushion) -> K + Send,
+    K: Ord + Send,
+    Cushion: Send,
+{
+    fn insert(
+        &mut self,
+        items: &mut Items<Cushion>,
+        cushion: Cushion,
+        source: usize,
+    ) -> DedupOutcome {
+        use std::collections::btree_map::Entry;
+
+        match self.seen.entry((self.key)(&cushion)) {
+            Entry::Vacant(entry) => {
+                let id = items.push(cushion);
+                entry.insert((id, source));
+                DedupOutcome::Inserted(id)
+            }
+            Entry::Occupied(mut entry) => {
+                let (id, seen_source) = *entry.get();
+
+                match &self.policy {
+                    DedupPolicy::FirstSeen => DedupOutcome::Dropped,
+                    DedupPolicy::SourcePriority(priority) => {
+                        let rank = |s| priority.iter().position(|&p| p == s).unwrap_or(usize::MAX);
+
+                        if rank(source) < rank(seen_source) {
+                            items[id] = cushion;
+                            entry.insert((id, source));
+                            DedupOutcome::Replaced(id)
+                        } else {
+                            DedupOutcome::Dropped
+                        }
+                    }
+                    DedupPolicy::Merge(f) => {
+                        f(&mut items[id], cushion);
+                        DedupOutcome::Replaced(id)
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert_all(
+        dedup: &mut impl Dedup<(&'static str, u32)>,
+        input: &[((&'static str, u32), usize)],
+    ) -> Vec<(&'static str, u32)> {
+        let mut items = Items::default();
+        for &(c, source) in input {
+            dedup.insert(&mut items, c, source);
+        }
+        // insertごとに高々1つしかidは増えない
+        (0..input.len())
+            .filter_map(|id| items.get(id).copied())
+            .collect()
+    }
+
+    const INPUT: [((&str, u32), usize); 4] =
+        [(("a", 1), 0), (("b", 2), 0), (("a", 3), 1), (("c", 4), 1)];
+
+    #[test]
+    fn first_seen() {
+        let mut dedup = Deduplicator::new(|c: &(&str, u32)| c.0, DedupPolicy::FirstSeen);
+
+        assert_eq!(
+            insert_all(&mut dedup, &INPUT),
+            [("a", 1), ("b", 2), ("c", 4)]
+        );
+    }
+
+    #[test]
+    fn source_priority() {
+        let mut dedup = Deduplicator::new(
+            |c: &(&str, u32)| c.0,
+            DedupPolicy::SourcePriority(vec![1, 0]),
+        );
+
+        assert_eq!(
+            insert_all(&mut dedup, &INPUT),
+            [("a", 3), ("b", 2), ("c", 4)]
+        );
+    }
+
+    #[test]
+    fn merge() {
+        let mut dedup = Deduplicator::new(
+            |c: &(&str, u32)| c.0,
+            DedupPolicy::Merge(Box::new(|lhs: &mut (&str, u32), rhs| lhs.1 += rhs.1)),
+        );
+
+        assert_eq!(
+            insert_all(&mut dedup, &INPUT),
+            [("a", 4), ("b", 2), ("c", 4)]
+        );
+    }
+}
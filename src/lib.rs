@@ -4,13 +4,27 @@ pub use async_trait;
 pub use color_eyre;
 pub use tokio_stream;
 
-#[cfg(feature = "log")]
 pub use tracing::Level;
 
 pub mod action;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(all(unix, feature = "daemon"))]
+pub mod daemon;
+pub mod field;
 pub mod filter;
 pub mod generator;
 pub mod launcher;
+#[cfg(feature = "log")]
+pub mod logging;
+#[cfg(feature = "matching")]
+pub mod matching;
+#[cfg(feature = "process")]
+pub mod process;
+pub mod query;
+#[cfg(feature = "registry")]
+pub mod registry;
+pub mod scorer;
 pub mod sorter;
 pub mod source;
 pub mod ui;
@@ -19,51 +33,49 @@ pub use crate::action::Action;
 pub use crate::filter::Filter;
 pub use crate::generator::Generator;
 pub use crate::launcher::Launcher;
+pub use crate::scorer::Scorer;
 pub use crate::sorter::Sorter;
 pub use crate::source::Source;
 pub use crate::ui::UI;
 
-use color_eyre::eyre::{OptionExt, Result};
-
-#[cfg(feature = "log")]
-fn init_subscriber_with_level(level: Level) -> Result<tracing_appender::non_blocking::WorkerGuard> {
-    use tracing_subscriber::fmt::format::FmtSpan;
-
-    fn db_dir() -> Option<std::path::PathBuf> {
-        let path = dirs::cache_dir().map(|p| p.join("ltrait/log"));
+#[cfg(feature = "derive")]
+pub use ltrait_derive::Cushion;
 
-        if let Some(parent) = path.as_ref().and_then(|p| p.parent()) {
-            std::fs::create_dir_all(parent).ok()?;
-        }
+use color_eyre::eyre::Result;
 
-        path
-    }
-
-    let file_appender = tracing_appender::rolling::hourly(
-        db_dir().ok_or_eyre("failed to get log dir")?,
-        "core.log",
-    );
-    let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
-
-    tracing_subscriber::fmt()
-        .with_writer(non_blocking)
-        .with_ansi(false)
-        .with_max_level(level) // set recorded log level
-        .with_span_events(FmtSpan::ACTIVE) // enable record span timing
-        .init();
+#[cfg(feature = "log")]
+pub use crate::logging::{Guard, Logging};
 
-    Ok(guard)
-}
+/// Does nothing without the `log` feature
+#[cfg(not(feature = "log"))]
+#[must_use]
+pub struct Guard;
 
 /// Install color_eyre and setup tracing(with tracing-log)
+///
+/// Without the `log` feature, only color_eyre is installed.
 /// ```
 /// use ltrait::{Level, setup};
 ///
 /// let _guard = setup(Level::TRACE);
 /// ```
-pub fn setup(log_level: Level) -> Result<tracing_appender::non_blocking::WorkerGuard> {
-    color_eyre::install()?;
-
+pub fn setup(log_level: Level) -> Result<Guard> {
     #[cfg(feature = "log")]
-    init_subscriber_with_level(log_level)
+    {
+        setup_with(Logging::new(log_level))
+    }
+
+    #[cfg(not(feature = "log"))]
+    {
+        let _ = log_level;
+        color_eyre::install()?;
+        Ok(Guard)
+    }
+}
+
+/// Install color_eyre and setup tracing as configured by `logging`
+#[cfg(feature = "log")]
+pub fn setup_with(logging: Logging) -> Result<Guard> {
+    color_eyre::install()?;
+    logging.init()
 }
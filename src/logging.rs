@@ -0,0 +1,194 @@
+//! A builder of the logging setup.
+//!
+//! ```no_run
+//! use ltrait::logging::{Format, Logging, Rotation};
+//!
+//! let _guard = ltrait::setup_with(
+//!     Logging::new(ltrait::Level::INFO)
+//!         .dir("/tmp/my-launcher/log")
+//!         .rotation(Rotation::Daily)
+//!         .max_files(7)
+//!         .filter("ltrait=debug,warn")
+//!         .format(Format::Json)
+//!         .stderr(true),
+//! );
+//! ```
+
+use color_eyre::eyre::{OptionExt, Result, eyre};
+use std::path::PathBuf;
+
+use tracing::Level;
+use tracing_subscriber::Layer;
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::SubscriberExt as _;
+use tracing_subscriber::util::SubscriberInitExt as _;
+
+type Subscriber = tracing_subscriber::layer::Layered<EnvFilter, tracing_subscriber::Registry>;
+type BoxedLayer = Box<dyn Layer<Subscriber> + Send + Sync>;
+
+/// How often a new log file is started
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    Minutely,
+    #[default]
+    Hourly,
+    Daily,
+    /// Writes to a single file
+    Never,
+}
+
+impl From<Rotation> for tracing_appender::rolling::Rotation {
+    fn from(rotation: Rotation) -> Self {
+        match rotation {
+            Rotation::Minutely => Self::MINUTELY,
+            Rotation::Hourly => Self::HOURLY,
+            Rotation::Daily => Self::DAILY,
+            Rotation::Never => Self::NEVER,
+        }
+    }
+}
+
+/// The format of log lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Plain,
+    /// A JSON object per line
+    Json,
+}
+
+/// Where and how the logs are written. The defaults are the same as [`setup`](crate::setup).
+#[derive(Debug, Clone)]
+pub struct Logging {
+    level: Level,
+    dir: Option<PathBuf>,
+    rotation: Rotation,
+    max_files: Option<usize>,
+    filter: Option<String>,
+    env: Option<String>,
+    format: Format,
+    stderr: bool,
+}
+
+impl Logging {
+    /// Records the events at `level` or above, into `<cache dir>/ltrait/log/core.log.*` rotated hourly
+    pub fn new(level: Level) -> Self {
+        Self {
+            level,
+            dir: None,
+            rotation: Rotation::default(),
+            max_files: None,
+            filter: None,
+            env: None,
+            format: Format::default(),
+            stderr: false,
+        }
+    }
+
+    /// The directory of the log files. The default is `<cache dir>/ltrait/log`.
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Deletes the oldest log files when there are more than `max_files`. By default, no file is deleted.
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.max_files = Some(max_files);
+        self
+    }
+
+    /// `RUST_LOG`-style directives like `ltrait=debug,warn`.
+    /// The level given to [`Logging::new`] is used for the targets not in the directives.
+    pub fn filter(mut self, directives: impl Into<String>) -> Self {
+        self.filter = Some(directives.into());
+        self
+    }
+
+    /// Reads the directives from the environment variable (e.g. `RUST_LOG`) if it is set,
+    /// in place of [`Logging::filter`].
+    pub fn env(mut self, var: impl Into<String>) -> Self {
+        self.env = Some(var.into());
+        self
+    }
+
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Also writes the logs to stderr. Don't use this with a UI that draws on the terminal.
+    pub fn stderr(mut self, flag: bool) -> Self {
+        self.stderr = flag;
+        self
+    }
+
+    /// Installs the global subscriber. Fails if a subscriber is already installed.
+    pub fn init(self) -> Result<Guard> {
+        let dir = match self.dir {
+            Some(dir) => dir,
+            None => dirs::cache_dir()
+                .map(|p| p.join("ltrait/log"))
+                .ok_or_eyre("failed to get log dir")?,
+        };
+        std::fs::create_dir_all(&dir)?;
+
+        let mut appender = tracing_appender::rolling::RollingFileAppender::builder()
+            .rotation(self.rotation.into())
+            .filename_prefix("core.log");
+        if let Some(max_files) = self.max_files {
+            appender = appender.max_log_files(max_files);
+        }
+        let (non_blocking, guard) = tracing_appender::non_blocking(appender.build(dir)?);
+
+        let directives = self
+            .env
+            .and_then(|var| std::env::var(var).ok())
+            .or(self.filter)
+            .unwrap_or_default();
+        let filter = EnvFilter::builder()
+            .with_default_directive(LevelFilter::from_level(self.level).into())
+            .parse(directives)?;
+
+        let mut layers: Vec<BoxedLayer> = vec![layer(self.format, non_blocking, false)];
+        if self.stderr {
+            layers.push(layer(self.format, std::io::stderr, true));
+        }
+
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(layers)
+            .try_init()
+            .map_err(|e| eyre!("failed to set the subscriber: {e}"))?;
+
+        Ok(Guard {
+            _inner: Some(guard),
+        })
+    }
+}
+
+fn layer<W>(format: Format, writer: W, ansi: bool) -> BoxedLayer
+where
+    W: for<'w> tracing_subscriber::fmt::MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(ansi)
+        .with_span_events(FmtSpan::ACTIVE); // enable record span timing
+
+    match format {
+        Format::Plain => layer.boxed(),
+        Format::Json => layer.json().boxed(),
+    }
+}
+
+/// Flushes the logs when dropped, keep it until the end of `main`.
+#[must_use]
+pub struct Guard {
+    _inner: Option<tracing_appender::non_blocking::WorkerGuard>,
+}
@@ -0,0 +1,541 @@
+//! Options shared by the matching filters and sorters: case sensitivity and Unicode folding.
+//!
+//! Both the input and the haystack are folded the same way before matching, so that e.g.
+//! `cafe` matches `Café` and `ﾌｧｲﾙ` matches `ファイル`.
+//!
+//! ```
+//! use ltrait::matching::{Case, MatchOptions};
+//!
+//! let options = MatchOptions::default().fold_diacritics(true).fold_width(true);
+//!
+//! assert!(options.pattern("cafe").is_match("Café"));
+//! assert!(options.pattern("ﾌｧｲﾙ").is_match("重要なファイル.txt"));
+//! // an uppercase letter in the input makes it case sensitive
+//! assert!(!options.pattern("Cafe").is_match("café"));
+//! assert!(!options.clone().case(Case::Sensitive).pattern("cafe").is_match("Café"));
+//! ```
+//!
+//! # Custom matchers
+//!
+//! [`PatternFilter`] and [`PatternSorter`] are the closure adapters of this module: the closure gets the input
+//! folded by the options as a [`Pattern`], built once per batch.
+//!
+//! ```
+//! use ltrait::matching::{MatchOptions, PatternFilter};
+//!
+//! let filter = PatternFilter::new(MatchOptions::default(), |path: &String, pattern| {
+//!     path.rsplit('/').next().is_some_and(|name| pattern.is_match(name))
+//! });
+//! ```
+//!
+//! To match some fields of [`Fields`](crate::field::Fields), pass [`field::selector`](crate::field::selector)
+//! as the transformer.
+//!
+//! ```
+//! use ltrait::Launcher;
+//! use ltrait::field::{Delimiter, Fields, selector, splitter};
+//! use ltrait::matching::{MatchFilter, MatchOptions};
+//! use ltrait::source::from_iter;
+//!
+//! let launcher = Launcher::default()
+//!     .add_source(from_iter(["1234 /usr/bin/foo"]), splitter(Delimiter::Whitespace))
+//!     .add_filter(
+//!         MatchFilter::new(MatchOptions::default().fold_width(true)),
+//!         selector("2..".parse().unwrap()),
+//!     );
+//! # let _: Launcher<Fields> = launcher;
+//! ```
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+use unicode_normalization::UnicodeNormalization as _;
+use unicode_normalization::char::is_combining_mark;
+
+use crate::filter::Filter;
+use crate::sorter::Sorter;
+
+/// Case sensitivity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Case {
+    /// Case insensitive unless the input has an uppercase letter
+    #[default]
+    Smart,
+    Sensitive,
+    Insensitive,
+}
+
+/// The Unicode normalization form applied before matching
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Normalization {
+    None,
+    /// Composes the decomposed characters, e.g. the file names from macOS
+    #[default]
+    Nfc,
+    /// Also folds compatibility characters, e.g. `①` to `1` and `ﬁ` to `fi`
+    Nfkc,
+}
+
+/// How the input and the haystack are compared. The default is smart case with NFC.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MatchOptions {
+    case: Case,
+    normalization: Normalization,
+    fold_diacritics: bool,
+    fold_width: bool,
+}
+
+impl MatchOptions {
+    pub fn case(mut self, case: Case) -> Self {
+        self.case = case;
+        self
+    }
+
+    pub fn normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Ignores the diacritics, e.g. `é` matches `e`. The (semi-)voiced sound marks of kana are kept.
+    pub fn fold_diacritics(mut self, fold: bool) -> Self {
+        self.fold_diacritics = fold;
+        self
+    }
+
+    /// Folds full-width ASCII to half-width and half-width katakana to full-width
+    pub fn fold_width(mut self, fold: bool) -> Self {
+        self.fold_width = fold;
+        self
+    }
+
+    /// Folds `input` to match haystacks with
+    pub fn pattern(&self, input: &str) -> Pattern {
+        let ignore_case = match self.case {
+            Case::Smart => !input.chars().any(char::is_uppercase),
+            Case::Sensitive => false,
+            Case::Insensitive => true,
+        };
+
+        Pattern {
+            text: self.fold(input, ignore_case),
+            ignore_case,
+            options: self.clone(),
+        }
+    }
+
+    fn fold(&self, s: &str, ignore_case: bool) -> String {
+        let mut s: String = match self.normalization {
+            Normalization::None => s.into(),
+            Normalization::Nfc => s.nfc().collect(),
+            Normalization::Nfkc => s.nfkc().collect(),
+        };
+
+        if self.fold_width {
+            s = fold_width(&s);
+        }
+        if self.fold_diacritics {
+            // 濁点と半濁点は残す(「が」が「か」にならないように)
+            s = s
+                .nfd()
+                .filter(|&c| !is_combining_mark(c) || matches!(c, '\u{3099}' | '\u{309A}'))
+                .nfc()
+                .collect();
+        }
+        if ignore_case {
+            s = s.to_lowercase();
+        }
+
+        s
+    }
+}
+
+fn fold_width(s: &str) -> String {
+    let mut halfwidth_kana = false;
+    let folded: String = s
+        .chars()
+        .flat_map(|c| {
+            let mut buf = [Some(c), None];
+            match c {
+                '\u{FF01}'..='\u{FF5E}' => buf[0] = char::from_u32(c as u32 - 0xFEE0),
+                '\u{3000}' => buf[0] = Some(' '),
+                // 半角カナの濁点は結合文字になる
+                '\u{FF61}'..='\u{FF9F}' => {
+                    halfwidth_kana = true;
+                    let mut nfkc = std::iter::once(c).nfkc();
+                    buf = [nfkc.next(), nfkc.next()];
+                }
+                _ => {}
+            }
+            buf.into_iter().flatten()
+        })
+        .collect();
+
+    if halfwidth_kana {
+        folded.nfc().collect()
+    } else {
+        folded
+    }
+}
+
+/// An input folded by [`MatchOptions::pattern`]
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    text: String,
+    ignore_case: bool,
+    options: MatchOptions,
+}
+
+impl Pattern {
+    /// The folded input
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Whether the input is matched case insensitively (it depends on the input with [`Case::Smart`])
+    pub fn ignore_case(&self) -> bool {
+        self.ignore_case
+    }
+
+    /// Folds `haystack` in the same way as the input
+    pub fn fold(&self, haystack: &str) -> String {
+        self.options.fold(haystack, self.ignore_case)
+    }
+
+    /// Whether the folded haystack contains the folded input
+    pub fn is_match(&self, haystack: &str) -> bool {
+        self.find(haystack).is_some()
+    }
+
+    /// The byte offset of the first match in the folded haystack
+    pub fn find(&self, haystack: &str) -> Option<usize> {
+        self.fold(haystack).find(&self.text)
+    }
+}
+
+/// Keeps the items containing every whitespace separated word of the input
+pub struct MatchFilter<Context> {
+    options: MatchOptions,
+
+    _marker: PhantomData<fn(&Context)>,
+}
+
+impl<Context> MatchFilter<Context> {
+    pub fn new(options: MatchOptions) -> Self {
+        Self {
+            options,
+            _marker: PhantomData,
+        }
+    }
+
+    fn patterns(&self, input: &str) -> Vec<Pattern> {
+        input
+            .split_whitespace()
+            .map(|word| self.options.pattern(word))
+            .collect()
+    }
+}
+
+impl<Context> Filter for MatchFilter<Context>
+where
+    Context: AsRef<str>,
+{
+    type Context = Context;
+
+    fn predicate(&self, ctx: &Self::Context, input: &str) -> bool {
+        self.predicate_batch(&[ctx], input)[0]
+    }
+
+    fn predicate_batch(&self, ctxs: &[&Self::Context], input: &str) -> Vec<bool> {
+        let patterns = self.patterns(input);
+        ctxs.iter()
+            .map(|ctx| {
+                patterns
+                    .iter()
+                    .all(|pattern| pattern.is_match(ctx.as_ref()))
+            })
+            .collect()
+    }
+}
+
+/// Sorts the items by the position of the match of the input, so that prefix matches come first.
+/// Ties (including no match) are broken by the shorter item.
+///
+/// The keys of a batch are built once in [`Sorter::prepare_batch`], not on every comparison.
+pub struct MatchSorter<Context> {
+    options: MatchOptions,
+    keys: RefCell<SortKeys>,
+
+    _marker: PhantomData<fn(&Context)>,
+}
+
+/// The sort keys of the items for the last input
+#[derive(Default)]
+struct SortKeys {
+    input: String,
+    pattern: Option<Pattern>,
+    keys: BTreeMap<String, (usize, usize)>,
+}
+
+fn sort_key(pattern: &Pattern, haystack: &str) -> (usize, usize) {
+    let folded = pattern.fold(haystack);
+    // Noneは後ろ
+    let position = folded.find(pattern.text()).unwrap_or(usize::MAX);
+    (position, folded.chars().count())
+}
+
+impl<Context> MatchSorter<Context> {
+    pub fn new(options: MatchOptions) -> Self {
+        Self {
+            options,
+            keys: RefCell::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Context> Sorter for MatchSorter<Context>
+where
+    Context: AsRef<str>,
+{
+    type Context = Context;
+
+    fn compare(&self, lhs: &Self::Context, rhs: &Self::Context, input: &str) -> Ordering {
+        let keys = self.keys.borrow();
+        match &keys.pattern {
+            Some(pattern) if keys.input == input => {
+                let key = |ctx: &Context| {
+                    let haystack = ctx.as_ref();
+                    keys.keys
+                        .get(haystack)
+                        .copied()
+                        .unwrap_or_else(|| sort_key(pattern, haystack))
+                };
+                key(lhs).cmp(&key(rhs))
+            }
+            // prepare_batchを経由せずに呼ばれた
+            _ => {
+                let pattern = self.options.pattern(input.trim());
+                sort_key(&pattern, lhs.as_ref()).cmp(&sort_key(&pattern, rhs.as_ref()))
+            }
+        }
+    }
+
+    fn prepare_batch(&self, ctxs: &[&Self::Context], input: &str) {
+        let mut keys = self.keys.borrow_mut();
+        // 前のbatchの項目とも比べるので、入力が同じ間はkeyを残す
+        if keys.pattern.is_none() || keys.input != input {
+            keys.input = input.into();
+            keys.pattern = Some(self.options.pattern(input.trim()));
+            keys.keys.clear();
+        }
+
+        let SortKeys { pattern, keys, .. } = &mut *keys;
+        let pattern = pattern.as_ref().unwrap();
+        for ctx in ctxs {
+            let haystack = ctx.as_ref();
+            if !keys.contains_key(haystack) {
+                keys.insert(haystack.into(), sort_key(pattern, haystack));
+            }
+        }
+    }
+}
+
+/// A filter with a closure that gets the input folded by the options
+pub struct PatternFilter<Context, F>
+where
+    F: Fn(&Context, &Pattern) -> bool,
+{
+    options: MatchOptions,
+    f: F,
+
+    _marker: PhantomData<fn(&Context)>,
+}
+
+impl<Context, F> PatternFilter<Context, F>
+where
+    F: Fn(&Context, &Pattern) -> bool,
+{
+    pub fn new(options: MatchOptions, f: F) -> Self {
+        Self {
+            options,
+            f,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Context, F> Filter for PatternFilter<Context, F>
+where
+    F: Fn(&Context, &Pattern) -> bool + Send,
+{
+    type Context = Context;
+
+    fn predicate(&self, ctx: &Self::Context, input: &str) -> bool {
+        self.predicate_batch(&[ctx], input)[0]
+    }
+
+    fn predicate_batch(&self, ctxs: &[&Self::Context], input: &str) -> Vec<bool> {
+        let pattern = self.options.pattern(input.trim());
+        ctxs.iter().map(|ctx| (self.f)(ctx, &pattern)).collect()
+    }
+}
+
+/// A sorter with a closure that gets the input folded by the options
+pub struct PatternSorter<Context, F>
+where
+    F: Fn(&Context, &Context, &Pattern) -> Ordering,
+{
+    options: MatchOptions,
+    f: F,
+    pattern: RefCell<Option<(String, Pattern)>>,
+
+    _marker: PhantomData<fn(&Context)>,
+}
+
+impl<Context, F> PatternSorter<Context, F>
+where
+    F: Fn(&Context, &Context, &Pattern) -> Ordering,
+{
+    pub fn new(options: MatchOptions, f: F) -> Self {
+        Self {
+            options,
+            f,
+            pattern: RefCell::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Context, F> Sorter for PatternSorter<Context, F>
+where
+    F: Fn(&Context, &Context, &Pattern) -> Ordering + Send,
+{
+    type Context = Context;
+
+    fn compare(&self, lhs: &Self::Context, rhs: &Self::Context, input: &str) -> Ordering {
+        match &*self.pattern.borrow() {
+            Some((last, pattern)) if last == input => (self.f)(lhs, rhs, pattern),
+            _ => (self.f)(lhs, rhs, &self.options.pattern(input.trim())),
+        }
+    }
+
+    fn prepare_batch(&self, _: &[&Self::Context], input: &str) {
+        let mut pattern = self.pattern.borrow_mut();
+        if pattern.as_ref().is_none_or(|(last, _)| last != input) {
+            *pattern = Some((input.into(), self.options.pattern(input.trim())));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn case() {
+        let options = MatchOptions::default();
+        assert!(options.pattern("foo").is_match("FOO"));
+        assert!(!options.pattern("Foo").is_match("FOO"));
+        assert!(options.pattern("FOO").is_match("FOO"));
+
+        let options = options.case(Case::Insensitive);
+        assert!(options.pattern("Foo").is_match("fOO"));
+    }
+
+    #[test]
+    fn normalization() {
+        // e + combining acute accent
+        let decomposed = "Cafe\u{301}";
+        let options = MatchOptions::default();
+        assert!(options.pattern("café").is_match(decomposed));
+        assert!(!options.pattern("cafe").is_match(decomposed));
+        assert!(
+            !options
+                .clone()
+                .normalization(Normalization::None)
+                .pattern("café")
+                .is_match(decomposed)
+        );
+
+        assert!(!options.pattern("1").is_match("①"));
+        let options = options.normalization(Normalization::Nfkc);
+        assert!(options.pattern("1").is_match("①"));
+        assert!(options.pattern("file").is_match("ﬁle"));
+    }
+
+    #[test]
+    fn diacritics() {
+        let options = MatchOptions::default().fold_diacritics(true);
+        assert!(options.pattern("cafe").is_match("Café"));
+        assert!(options.pattern("café").is_match("cafe"));
+        assert!(options.pattern("angstrom").is_match("Ångström"));
+
+        // 濁点は落とさない
+        assert!(options.pattern("がっこう").is_match("がっこう"));
+        assert!(!options.pattern("かっこう").is_match("がっこう"));
+        assert!(!options.pattern("ハン").is_match("パン"));
+    }
+
+    #[test]
+    fn width() {
+        let options = MatchOptions::default().fold_width(true);
+        assert!(options.pattern("abc1").is_match("ＡＢＣ１"));
+        assert!(options.pattern("ｆｏｏ").is_match("foo bar"));
+        assert!(options.pattern("a b").is_match("a\u{3000}b"));
+        assert!(options.pattern("ｶﾞｲﾄﾞ").is_match("ガイド.pdf"));
+        assert!(options.pattern("ガイド").is_match("ｶﾞｲﾄﾞ.pdf"));
+        assert!(options.pattern("ﾊﾟﾝ").is_match("パン"));
+        assert!(!options.pattern("ﾊﾟﾝ").is_match("ハン"));
+
+        assert!(!MatchOptions::default().pattern("abc").is_match("ａｂｃ"));
+    }
+
+    #[test]
+    fn filter_and_sorter() {
+        let filter = MatchFilter::<String>::new(MatchOptions::default());
+        let items = ["Foo Bar".to_string(), "bar".into(), "baz".into()];
+        let ctxs: Vec<_> = items.iter().collect();
+        assert_eq!(
+            filter.predicate_batch(&ctxs, " bar  foo "),
+            [true, false, false]
+        );
+        assert_eq!(filter.predicate_batch(&ctxs, ""), [true, true, true]);
+
+        let sorter = MatchSorter::<&str>::new(MatchOptions::default());
+        let mut items = vec!["xxbar", "none", "bar baz", "barn", "xbar"];
+        items.sort_by(|a, b| sorter.compare(a, b, "bar"));
+        assert_eq!(items, ["barn", "bar baz", "xbar", "xxbar", "none"]);
+        assert!(sorter.keys.borrow().keys.is_empty());
+
+        // 入力が変わったらkeyを作り直す
+        sorter.prepare_batch(&items.iter().collect::<Vec<_>>(), "baz");
+        assert_eq!(sorter.keys.borrow().keys.len(), 5);
+        items.sort_by(|a, b| sorter.compare(a, b, "baz"));
+        assert_eq!(items, ["bar baz", "barn", "xbar", "none", "xxbar"]);
+    }
+
+    #[test]
+    fn pattern_adapters() {
+        let options = MatchOptions::default().fold_width(true);
+        let filter = PatternFilter::new(options.clone(), |x: &&str, pattern| {
+            pattern.find(x) == Some(0)
+        });
+        let items = ["ＦＯＯbar", "barfoo", "foo"];
+        let ctxs: Vec<_> = items.iter().collect();
+        assert_eq!(filter.predicate_batch(&ctxs, "foo "), [true, false, true]);
+
+        let sorter = PatternSorter::new(options, |a: &&str, b: &&str, pattern| {
+            pattern.find(a).cmp(&pattern.find(b)).reverse()
+        });
+        let mut items = vec!["bar", "foo", "ａfoo"];
+        sorter.prepare_batch(&items.iter().collect::<Vec<_>>(), "ｆｏｏ");
+        items.sort_by(|a, b| sorter.compare(a, b, "ｆｏｏ"));
+        assert_eq!(items, ["ａfoo", "foo", "bar"]);
+    }
+}
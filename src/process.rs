@@ -0,0 +1,518 @@
+//! Filters, scorers and sorters implemented by a child process, e.g. ranking logic written in another language.
+//!
+//! The items of a batch are sent to the process in chunks, as a JSON object on a line:
+//!
+//! ```text
+//! {"input": "foo", "items": [<item>, <item>, ...]}
+//! ```
+//!
+//! where each item is the `Context` of the filter, scorer or sorter serialized with serde.
+//! The process replies with a JSON array on a line, one value per item, in the same order:
+//! booleans for [`ProcessFilter`] (true to keep the item), numbers for [`ProcessScorer`]
+//! and sort keys (numbers, smaller first) for [`ProcessSorter`].
+//!
+//! If the process does not reply within the timeout, replies something invalid, or exits,
+//! it is killed and the fallback value is used from then on.
+//!
+//! Filters, scorers and sorters are synchronous, so the batcher waits for the reply.
+//! On a multi-thread tokio runtime the wait is done with [`tokio::task::block_in_place`],
+//! so the other tasks keep running meanwhile.
+//!
+//! ```no_run
+//! # fn f() -> ltrait::color_eyre::Result<()> {
+//! use ltrait::process::ProcessScorer;
+//! use std::process::Command;
+//! use std::time::Duration;
+//!
+//! let mut command = Command::new("python3");
+//! command.arg("rank.py");
+//!
+//! let scorer = ProcessScorer::<String>::spawn(command)?
+//!     .timeout(Duration::from_millis(200))
+//!     .fallback(0.0);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A [`ProcessSorter`] asks the keys of the whole batch before the items are compared.
+//! To blend the ranking with other signals, use [`ProcessScorer`] instead.
+
+use color_eyre::eyre::{OptionExt, Result, bail, ensure, eyre};
+use serde::{Serialize, de::DeserializeOwned};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, BufReader, Write};
+use std::marker::PhantomData;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tracing::warn;
+
+use crate::filter::Filter;
+use crate::scorer::Scorer;
+use crate::sorter::Sorter;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+const DEFAULT_BATCH_SIZE: usize = 1000;
+
+/// A running child process. Dropping it kills the process.
+struct Worker {
+    child: Child,
+    // 書き込みでブロックしてもtimeoutできるように別スレッドで書く
+    requests: Sender<Vec<u8>>,
+    replies: Receiver<String>,
+}
+
+impl Worker {
+    fn spawn(mut command: Command) -> Result<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_eyre("failed to get stdin of the child process")?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_eyre("failed to get stdout of the child process")?;
+
+        let (requests, requests_rx) = mpsc::channel::<Vec<u8>>();
+        std::thread::spawn(move || {
+            for request in requests_rx {
+                if stdin
+                    .write_all(&request)
+                    .and_then(|_| stdin.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let (replies_tx, replies) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if replies_tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            child,
+            requests,
+            replies,
+        })
+    }
+
+    fn call<T, R>(&self, input: &str, items: &[&T], timeout: Duration) -> Result<Vec<R>>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        #[derive(Serialize)]
+        struct Request<'a, T> {
+            input: &'a str,
+            items: &'a [&'a T],
+        }
+
+        let mut request = serde_json::to_vec(&Request { input, items })?;
+        request.push(b'\n');
+        self.requests
+            .send(request)
+            .map_err(|_| eyre!("the process is not reading its stdin"))?;
+
+        let reply = match blocking(|| self.replies.recv_timeout(timeout)) {
+            Ok(reply) => reply,
+            Err(RecvTimeoutError::Timeout) => bail!("the process did not reply in {timeout:?}"),
+            Err(RecvTimeoutError::Disconnected) => bail!("the process exited"),
+        };
+
+        let reply: Vec<R> = serde_json::from_str(&reply)?;
+        ensure!(
+            reply.len() == items.len(),
+            "the process replied {} values for {} items",
+            reply.len(),
+            items.len()
+        );
+
+        Ok(reply)
+    }
+}
+
+/// Runs `f`, which blocks, without stalling the other tasks of a multi-thread tokio runtime
+fn blocking<R>(f: impl FnOnce() -> R) -> R {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        // current_threadではblock_in_placeできないので、そのまま待つ
+        _ => f(),
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The part shared by [`ProcessFilter`], [`ProcessScorer`] and [`ProcessSorter`]
+struct Process {
+    /// None after the process failed
+    worker: Mutex<Option<Worker>>,
+    timeout: Duration,
+    batch_size: usize,
+}
+
+impl Process {
+    fn spawn(command: Command) -> Result<Self> {
+        Ok(Self {
+            worker: Mutex::new(Some(Worker::spawn(command)?)),
+            timeout: DEFAULT_TIMEOUT,
+            batch_size: DEFAULT_BATCH_SIZE,
+        })
+    }
+
+    /// Returns None if the process has failed
+    fn call<T, R>(&self, input: &str, items: &[&T]) -> Option<Vec<R>>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let mut worker = self.worker.lock().unwrap_or_else(|e| e.into_inner());
+
+        let result = {
+            let w = worker.as_ref()?;
+            items.chunks(self.batch_size.max(1)).try_fold(
+                Vec::with_capacity(items.len()),
+                |mut acc, chunk| {
+                    acc.extend(w.call(input, chunk, self.timeout)?);
+                    Ok::<_, color_eyre::Report>(acc)
+                },
+            )
+        };
+
+        match result {
+            Ok(values) => Some(values),
+            Err(e) => {
+                warn!("The process failed, falling back: {e}");
+                // 返事がずれるので、一度失敗したら使わない
+                *worker = None;
+                None
+            }
+        }
+    }
+}
+
+/// A [`Filter`] decided by a child process. See [the module documentation](self) for the protocol.
+pub struct ProcessFilter<T> {
+    process: Process,
+    fallback: bool,
+
+    _marker: PhantomData<fn(&T)>,
+}
+
+impl<T> ProcessFilter<T> {
+    pub fn spawn(command: Command) -> Result<Self> {
+        Ok(Self {
+            process: Process::spawn(command)?,
+            fallback: true,
+            _marker: PhantomData,
+        })
+    }
+
+    /// How long to wait for a reply to a chunk. The default is 1 second.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.process.timeout = timeout;
+        self
+    }
+
+    /// The maximum number of items sent at once. The default is 1000.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.process.batch_size = batch_size;
+        self
+    }
+
+    /// Whether items are kept after the process failed. The default is true.
+    pub fn fallback(mut self, keep: bool) -> Self {
+        self.fallback = keep;
+        self
+    }
+}
+
+impl<T> Filter for ProcessFilter<T>
+where
+    T: Serialize,
+{
+    type Context = T;
+
+    fn predicate(&self, ctx: &Self::Context, input: &str) -> bool {
+        self.predicate_batch(&[ctx], input)[0]
+    }
+
+    fn predicate_batch(&self, ctxs: &[&Self::Context], input: &str) -> Vec<bool> {
+        self.process
+            .call(input, ctxs)
+            .unwrap_or_else(|| vec![self.fallback; ctxs.len()])
+    }
+}
+
+/// A [`Scorer`] computed by a child process. See [the module documentation](self) for the protocol.
+pub struct ProcessScorer<T> {
+    process: Process,
+    fallback: f64,
+
+    _marker: PhantomData<fn(&T)>,
+}
+
+impl<T> ProcessScorer<T> {
+    pub fn spawn(command: Command) -> Result<Self> {
+        Ok(Self {
+            process: Process::spawn(command)?,
+            fallback: 0.0,
+            _marker: PhantomData,
+        })
+    }
+
+    /// How long to wait for a reply to a chunk. The default is 1 second.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.process.timeout = timeout;
+        self
+    }
+
+    /// The maximum number of items sent at once. The default is 1000.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.process.batch_size = batch_size;
+        self
+    }
+
+    /// The score of items after the process failed. The default is 0.0.
+    pub fn fallback(mut self, score: f64) -> Self {
+        self.fallback = score;
+        self
+    }
+}
+
+impl<T> Scorer for ProcessScorer<T>
+where
+    T: Serialize,
+{
+    type Context = T;
+
+    fn score(&self, ctx: &Self::Context, input: &str) -> f64 {
+        self.score_batch(&[ctx], input)[0]
+    }
+
+    fn score_batch(&self, ctxs: &[&Self::Context], input: &str) -> Vec<f64> {
+        self.process
+            .call(input, ctxs)
+            .unwrap_or_else(|| vec![self.fallback; ctxs.len()])
+    }
+}
+
+/// A [`Sorter`] by the keys from a child process. See [the module documentation](self) for the protocol.
+///
+/// The keys are asked once per batch by [`Sorter::prepare_batch`] and kept by the item until the input changes,
+/// so the items are compared without talking to the process. `T` is the key of the cache, so it has to be `Ord` and `Clone`.
+pub struct ProcessSorter<T> {
+    process: Process,
+    fallback: f64,
+    /// The input and the keys of the items for it
+    keys: RefCell<(String, BTreeMap<T, f64>)>,
+}
+
+impl<T> ProcessSorter<T> {
+    pub fn spawn(command: Command) -> Result<Self> {
+        Ok(Self {
+            process: Process::spawn(command)?,
+            fallback: 0.0,
+            keys: RefCell::new((String::new(), BTreeMap::new())),
+        })
+    }
+
+    /// How long to wait for a reply to a chunk. The default is 1 second.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.process.timeout = timeout;
+        self
+    }
+
+    /// The maximum number of items sent at once. The default is 1000.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.process.batch_size = batch_size;
+        self
+    }
+
+    /// The key of items after the process failed. The default is 0.0.
+    pub fn fallback(mut self, key: f64) -> Self {
+        self.fallback = key;
+        self
+    }
+}
+
+impl<T> ProcessSorter<T>
+where
+    T: Serialize + Ord + Clone,
+{
+    /// Asks the keys of the items not known yet
+    fn fetch(&self, ctxs: &[&T], input: &str) {
+        let mut keys = self.keys.borrow_mut();
+        if keys.0 != input {
+            *keys = (input.into(), BTreeMap::new());
+        }
+
+        let missing: BTreeSet<_> = ctxs
+            .iter()
+            .copied()
+            .filter(|ctx| !keys.1.contains_key(ctx))
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+
+        let missing: Vec<_> = missing.into_iter().collect();
+        let values = self
+            .process
+            .call(input, &missing)
+            .unwrap_or_else(|| vec![self.fallback; missing.len()]);
+        keys.1.extend(missing.into_iter().cloned().zip(values));
+    }
+
+    fn key(&self, ctx: &T, input: &str) -> f64 {
+        let cached = || {
+            let keys = self.keys.borrow();
+            (keys.0 == input)
+                .then(|| keys.1.get(ctx).copied())
+                .flatten()
+        };
+
+        cached().unwrap_or_else(|| {
+            // prepare_batchを通っていないitem
+            self.fetch(&[ctx], input);
+            cached().unwrap_or(self.fallback)
+        })
+    }
+}
+
+impl<T> Sorter for ProcessSorter<T>
+where
+    T: Serialize + Ord + Clone + Send,
+{
+    type Context = T;
+
+    fn compare(&self, lhs: &Self::Context, rhs: &Self::Context, input: &str) -> std::cmp::Ordering {
+        self.key(lhs, input).total_cmp(&self.key(rhs, input))
+    }
+
+    fn prepare_batch(&self, ctxs: &[&Self::Context], input: &str) {
+        self.fetch(ctxs, input);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        command
+    }
+
+    #[test]
+    fn filter() -> Result<()> {
+        // 2件ずつ送られてくる
+        let filter = ProcessFilter::spawn(sh("while read -r l; do echo '[true, false]'; done"))?
+            .batch_size(2);
+
+        let items = ["a", "b", "c", "d"];
+        let ctxs: Vec<_> = items.iter().collect();
+        assert_eq!(
+            filter.predicate_batch(&ctxs, ""),
+            [true, false, true, false]
+        );
+
+        // 1件に2件返すのは壊れているとみなす
+        assert!(filter.predicate(&"a", ""));
+        assert!(filter.process.worker.lock().unwrap().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn scorer() -> Result<()> {
+        let scorer = ProcessScorer::spawn(sh("while read -r l; do echo '[1.5]'; done"))?;
+        assert_eq!(scorer.score(&1, ""), 1.5);
+
+        let scorer = ProcessScorer::spawn(sh("exit 0"))?.fallback(-1.0);
+        assert_eq!(scorer.score_batch(&[&1, &2], ""), [-1.0, -1.0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn sorter() -> Result<()> {
+        // 項目の長さをkeyとして返す
+        let sorter = ProcessSorter::spawn(sh(
+            r#"while read -r l; do echo "$l" | sed -e 's/.*"items":\[//' -e 's/\]}$//' | tr ',' '\n' | awk 'BEGIN { printf "[" } { if (NR > 1) printf ","; printf length($0) } END { print "]" }'; done"#,
+        ))?
+        .batch_size(2);
+
+        let mut items = vec!["ccc", "a", "bbbb", "dd"];
+        let ctxs: Vec<_> = items.iter().collect();
+        sorter.prepare_batch(&ctxs, "");
+        assert_eq!(sorter.keys.borrow().1.len(), 4);
+
+        items.sort_by(|a, b| sorter.compare(a, b, ""));
+        assert_eq!(items, ["a", "dd", "ccc", "bbbb"]);
+        // prepare_batchを通っていないitemはその場で聞く
+        assert!(sorter.compare(&"eeeeee", &"a", "").is_gt());
+
+        let sorter = ProcessSorter::spawn(sh("exit 0"))?.fallback(1.0);
+        assert!(sorter.compare(&"a", &"bb", "").is_eq());
+
+        Ok(())
+    }
+
+    #[test]
+    fn timeout() -> Result<()> {
+        let filter = ProcessFilter::spawn(sh("sleep 10"))?
+            .timeout(Duration::from_millis(50))
+            .fallback(false);
+
+        let start = std::time::Instant::now();
+        assert!(!filter.predicate(&"a", ""));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn no_blocking() -> Result<()> {
+        let filter = ProcessFilter::spawn(sh("sleep 10"))?
+            .timeout(Duration::from_millis(500))
+            .fallback(false);
+
+        // workerが1つでも、返事を待つ間に他のtaskが進む
+        let waiting = tokio::spawn(async move { filter.predicate(&"a", "") });
+        let ticks = tokio::spawn(async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            std::time::Instant::now()
+        });
+
+        let ticked = ticks.await?;
+        assert!(!waiting.await?);
+        assert!(ticked.elapsed() > Duration::from_millis(200));
+
+        Ok(())
+    }
+}
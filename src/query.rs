@@ -0,0 +1,790 @@
+//! A query syntax scoped to the fields of structured items, like `name:foo tag:work size>10M modified<7d`.
+//!
+//! A [`Schema`] exposes the fields of the cushion through transformers, and [`QueryFilter`] keeps the items
+//! matching every term of the input. The terms are
+//!
+//! | term            | matches                                                                                       |
+//! | --------------- | --------------------------------------------------------------------------------------------- |
+//! | `foo`           | items whose default fields contain `foo` (all the text fields if none are set)               |
+//! | `name:foo`      | text: contains `foo`, tags: has the tag `foo`, others: equals                                 |
+//! | `name=foo`      | equals `foo`                                                                                  |
+//! | `size>10M`      | numbers, sizes (`K`, `M`, `G`, `T`, in 1024) and times with `<`, `<=`, `>`, `>=`              |
+//! | `modified<7d`   | times are compared by the age (`s`, `m`, `h`, `d`, `w`, `y`), so this is "in the last 7 days" |
+//! | `-tag:work`     | negates the term                                                                              |
+//! | `name:"a b"`    | quotes a value with spaces                                                                    |
+//!
+//! Terms that can't be used, e.g. an unknown field or an invalid size, are reported as [`Diagnostic`]s
+//! by [`Schema::parse`], so that a UI can show them. They are ignored in the filtering,
+//! except that a term with an unknown field is matched as a plain word (e.g. a URL).
+//!
+//! ```
+//! use ltrait::query::Schema;
+//! use std::time::{Duration, SystemTime};
+//!
+//! struct File {
+//!     name: String,
+//!     tags: Vec<String>,
+//!     size: u64,
+//!     modified: SystemTime,
+//! }
+//!
+//! let schema = Schema::new()
+//!     .text("name", |f: &File| f.name.clone())
+//!     .tags("tag", |f: &File| f.tags.clone())
+//!     .size("size", |f: &File| f.size)
+//!     .time("modified", |f: &File| f.modified);
+//!
+//! let file = File {
+//!     name: "report.pdf".into(),
+//!     tags: vec!["work".into()],
+//!     size: 20 << 20,
+//!     modified: SystemTime::now() - Duration::from_secs(60 * 60),
+//! };
+//!
+//! assert!(schema.parse("name:report tag:work size>10M modified<7d").matches(&file));
+//! assert!(!schema.parse("report -tag:work").matches(&file));
+//!
+//! let query = schema.parse("owner:me size>big");
+//! assert_eq!(query.diagnostics()[0].to_string(), "unknown field `owner`");
+//! assert_eq!(query.diagnostics()[1].span, 9..17);
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use tracing::debug;
+
+use crate::filter::Filter;
+
+type Getter<Cushion, T> = Box<dyn Fn(&Cushion) -> T + Send + Sync>;
+
+enum Field<Cushion> {
+    Text(Getter<Cushion, String>),
+    Tags(Getter<Cushion, Vec<String>>),
+    Number(Getter<Cushion, f64>),
+    Size(Getter<Cushion, u64>),
+    Time(Getter<Cushion, SystemTime>),
+}
+
+impl<Cushion> Field<Cushion> {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Text(_) => "text",
+            Self::Tags(_) => "tags",
+            Self::Number(_) => "number",
+            Self::Size(_) => "size",
+            Self::Time(_) => "time",
+        }
+    }
+}
+
+/// The named fields of the cushion that the query can refer to
+pub struct Schema<Cushion> {
+    fields: BTreeMap<String, Field<Cushion>>,
+    /// The fields matched by the terms without a field
+    defaults: Vec<String>,
+    #[cfg(feature = "matching")]
+    options: crate::matching::MatchOptions,
+}
+
+impl<Cushion> Default for Schema<Cushion> {
+    fn default() -> Self {
+        Self {
+            fields: BTreeMap::new(),
+            defaults: vec![],
+            #[cfg(feature = "matching")]
+            options: Default::default(),
+        }
+    }
+}
+
+impl<Cushion> Schema<Cushion> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn field(mut self, name: impl Into<String>, field: Field<Cushion>) -> Self {
+        self.fields.insert(name.into(), field);
+        self
+    }
+
+    /// A text field. `:` matches a substring (smart case), and `=` the whole text.
+    pub fn text<F>(self, name: impl Into<String>, transformer: F) -> Self
+    where
+        F: Fn(&Cushion) -> String + Send + Sync + 'static,
+    {
+        self.field(name, Field::Text(Box::new(transformer)))
+    }
+
+    /// A list of tags. `:` and `=` match one of the tags.
+    pub fn tags<F>(self, name: impl Into<String>, transformer: F) -> Self
+    where
+        F: Fn(&Cushion) -> Vec<String> + Send + Sync + 'static,
+    {
+        self.field(name, Field::Tags(Box::new(transformer)))
+    }
+
+    pub fn number<F>(self, name: impl Into<String>, transformer: F) -> Self
+    where
+        F: Fn(&Cushion) -> f64 + Send + Sync + 'static,
+    {
+        self.field(name, Field::Number(Box::new(transformer)))
+    }
+
+    /// A size in bytes, written like `10M` in the query
+    pub fn size<F>(self, name: impl Into<String>, transformer: F) -> Self
+    where
+        F: Fn(&Cushion) -> u64 + Send + Sync + 'static,
+    {
+        self.field(name, Field::Size(Box::new(transformer)))
+    }
+
+    /// A time, compared by the age written like `7d` in the query
+    pub fn time<F>(self, name: impl Into<String>, transformer: F) -> Self
+    where
+        F: Fn(&Cushion) -> SystemTime + Send + Sync + 'static,
+    {
+        self.field(name, Field::Time(Box::new(transformer)))
+    }
+
+    /// The text fields matched by the terms without a field. The default is all the text fields.
+    ///
+    /// Names that are unknown or not text fields are reported by [`Schema::parse`] at the first term without a field.
+    pub fn default_fields<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.defaults = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The options of the text matching
+    #[cfg(feature = "matching")]
+    pub fn match_options(mut self, options: crate::matching::MatchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Parses `input` into the terms and the diagnostics
+    pub fn parse(&self, input: &str) -> Query<'_, Cushion> {
+        let now = SystemTime::now();
+        let mut terms = vec![];
+        let mut diagnostics = vec![];
+        let mut first_word = None;
+
+        for (token, span) in tokenize(input) {
+            match self.term(&token, span.clone(), now) {
+                Ok(term) => {
+                    if term.field.is_none() {
+                        first_word.get_or_insert(span);
+                    }
+                    terms.push(term);
+                }
+                Err(diagnostic) => {
+                    // URLなどかもしれないので普通の単語として扱う
+                    if diagnostic.unknown_field {
+                        terms.push(self.word(&token));
+                        first_word.get_or_insert(span);
+                    }
+                    diagnostics.push(diagnostic);
+                }
+            }
+        }
+
+        // 単語がなければdefault fieldsは使わないので報告しない
+        if let Some(span) = first_word {
+            for name in &self.defaults {
+                let message = match self.fields.get(name) {
+                    None => format!("unknown default field `{name}`"),
+                    Some(Field::Text(_)) => continue,
+                    Some(field) => format!(
+                        "default field `{name}` ({}) is not a text field",
+                        field.kind()
+                    ),
+                };
+                diagnostics.push(Diagnostic {
+                    span: span.clone(),
+                    message,
+                    unknown_field: false,
+                });
+            }
+        }
+
+        Query {
+            schema: self,
+            terms,
+            diagnostics,
+        }
+    }
+
+    fn word(&self, token: &Token) -> Term {
+        Term {
+            field: None,
+            negated: token.negated,
+            test: Test::Contains(self.needle(&token.raw)),
+        }
+    }
+
+    fn term(&self, token: &Token, span: Range<usize>, now: SystemTime) -> Result<Term, Diagnostic> {
+        let Some((name, op, value)) = &token.scoped else {
+            return Ok(self.word(token));
+        };
+        let op = *op;
+
+        let Some(field) = self.fields.get(name) else {
+            return Err(Diagnostic {
+                span,
+                message: format!("unknown field `{name}`"),
+                unknown_field: true,
+            });
+        };
+        let error = |message: String| Diagnostic {
+            span: span.clone(),
+            message,
+            unknown_field: false,
+        };
+        let unsupported = || {
+            error(format!(
+                "`{name}` ({}) can't be used with `{op}`",
+                field.kind()
+            ))
+        };
+
+        let test = match field {
+            Field::Text(_) | Field::Tags(_) => match op {
+                Op::Contains if matches!(field, Field::Text(_)) => {
+                    Test::Contains(self.needle(value))
+                }
+                Op::Contains | Op::Eq => Test::Equals(self.needle(value)),
+                _ => return Err(unsupported()),
+            },
+            Field::Number(_) => {
+                let value = value
+                    .parse()
+                    .map_err(|_| error(format!("invalid number `{value}`")))?;
+                Test::Compare(op.ordering(), Value::Number(value))
+            }
+            Field::Size(_) => {
+                let value =
+                    parse_size(value).ok_or_else(|| error(format!("invalid size `{value}`")))?;
+                Test::Compare(op.ordering(), Value::Size(value))
+            }
+            Field::Time(_) => {
+                let age =
+                    parse_age(value).ok_or_else(|| error(format!("invalid age `{value}`")))?;
+                if matches!(op, Op::Contains | Op::Eq) {
+                    return Err(unsupported());
+                }
+                // 年齢で比較するので時刻では逆になる
+                Test::Compare(
+                    op.ordering().reverse(),
+                    Value::Time(now.checked_sub(age).unwrap_or(SystemTime::UNIX_EPOCH)),
+                )
+            }
+        };
+
+        Ok(Term {
+            field: Some(name.clone()),
+            negated: token.negated,
+            test,
+        })
+    }
+
+    #[cfg(feature = "matching")]
+    fn needle(&self, s: &str) -> Needle {
+        self.options.pattern(s)
+    }
+
+    #[cfg(not(feature = "matching"))]
+    fn needle(&self, s: &str) -> Needle {
+        let ignore_case = !s.chars().any(char::is_uppercase);
+        Needle {
+            text: if ignore_case {
+                s.to_lowercase()
+            } else {
+                s.into()
+            },
+            ignore_case,
+        }
+    }
+}
+
+#[cfg(feature = "matching")]
+type Needle = crate::matching::Pattern;
+
+#[cfg(not(feature = "matching"))]
+#[derive(Debug)]
+struct Needle {
+    text: String,
+    ignore_case: bool,
+}
+
+#[cfg(not(feature = "matching"))]
+impl Needle {
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn fold(&self, haystack: &str) -> String {
+        if self.ignore_case {
+            haystack.to_lowercase()
+        } else {
+            haystack.into()
+        }
+    }
+}
+
+/// A problem with a term of the query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The byte range of the term in the input
+    pub span: Range<usize>,
+    pub message: String,
+
+    unknown_field: bool,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// A parsed query. See [`Schema::parse`].
+pub struct Query<'a, Cushion> {
+    schema: &'a Schema<Cushion>,
+    terms: Vec<Term>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<Cushion> Query<'_, Cushion> {
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Whether `cushion` matches every term
+    pub fn matches(&self, cushion: &Cushion) -> bool {
+        self.terms
+            .iter()
+            .all(|term| self.test(term, cushion) != term.negated)
+    }
+
+    fn test(&self, term: &Term, cushion: &Cushion) -> bool {
+        let Some(name) = &term.field else {
+            let Test::Contains(needle) = &term.test else {
+                unreachable!()
+            };
+            let is_match = |field: &Field<Cushion>| match field {
+                Field::Text(get) => needle.fold(&get(cushion)).contains(needle.text()),
+                _ => false,
+            };
+            if self.schema.defaults.is_empty() {
+                return self.schema.fields.values().any(is_match);
+            }
+            return self
+                .schema
+                .defaults
+                .iter()
+                .filter_map(|name| self.schema.fields.get(name))
+                .any(is_match);
+        };
+
+        match (&self.schema.fields[name], &term.test) {
+            (Field::Text(get), Test::Contains(needle)) => {
+                needle.fold(&get(cushion)).contains(needle.text())
+            }
+            (Field::Text(get), Test::Equals(needle)) => needle.fold(&get(cushion)) == needle.text(),
+            (Field::Tags(get), Test::Equals(needle)) => get(cushion)
+                .iter()
+                .any(|tag| needle.fold(tag) == needle.text()),
+            (Field::Number(get), Test::Compare(ords, Value::Number(value))) => get(cushion)
+                .partial_cmp(value)
+                .is_some_and(|ord| ords.contains(&ord)),
+            (Field::Size(get), Test::Compare(ords, Value::Size(value))) => {
+                ords.contains(&get(cushion).cmp(value))
+            }
+            (Field::Time(get), Test::Compare(ords, Value::Time(value))) => {
+                ords.contains(&get(cushion).cmp(value))
+            }
+            _ => unreachable!("the test is made for the kind of the field"),
+        }
+    }
+}
+
+/// Keeps the items matching the query. Diagnostics are only logged; use [`Schema::parse`] to show them.
+pub struct QueryFilter<Cushion> {
+    schema: Arc<Schema<Cushion>>,
+}
+
+impl<Cushion> QueryFilter<Cushion> {
+    pub fn new(schema: Arc<Schema<Cushion>>) -> Self {
+        Self { schema }
+    }
+}
+
+impl<Cushion> Filter for QueryFilter<Cushion>
+where
+    Cushion: Send + Sync,
+{
+    type Context = Cushion;
+
+    fn predicate(&self, ctx: &Self::Context, input: &str) -> bool {
+        self.predicate_batch(&[ctx], input)[0]
+    }
+
+    fn predicate_batch(&self, ctxs: &[&Self::Context], input: &str) -> Vec<bool> {
+        let query = self.schema.parse(input);
+        if !query.diagnostics().is_empty() {
+            debug!("query diagnostics: {:?}", query.diagnostics());
+        }
+        ctxs.iter().map(|ctx| query.matches(ctx)).collect()
+    }
+}
+
+#[derive(Debug)]
+struct Term {
+    /// None for a plain word
+    field: Option<String>,
+    negated: bool,
+    test: Test,
+}
+
+#[derive(Debug)]
+enum Test {
+    Contains(Needle),
+    Equals(Needle),
+    /// Matches if the ordering of the field to the value is one of them
+    Compare(OrderingSet, Value),
+}
+
+#[derive(Debug)]
+enum Value {
+    Number(f64),
+    Size(u64),
+    Time(SystemTime),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Contains,
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn ordering(self) -> OrderingSet {
+        match self {
+            Self::Contains | Self::Eq => OrderingSet(&[Ordering::Equal]),
+            Self::Lt => OrderingSet(&[Ordering::Less]),
+            Self::Le => OrderingSet(&[Ordering::Less, Ordering::Equal]),
+            Self::Gt => OrderingSet(&[Ordering::Greater]),
+            Self::Ge => OrderingSet(&[Ordering::Greater, Ordering::Equal]),
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Contains => ":",
+            Self::Eq => "=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        })
+    }
+}
+
+/// The orderings accepted by an operator
+#[derive(Debug)]
+struct OrderingSet(&'static [Ordering]);
+
+impl OrderingSet {
+    fn contains(&self, ord: &Ordering) -> bool {
+        self.0.contains(ord)
+    }
+
+    fn reverse(self) -> Self {
+        match self.0 {
+            [Ordering::Less] => Self(&[Ordering::Greater]),
+            [Ordering::Greater] => Self(&[Ordering::Less]),
+            [Ordering::Less, Ordering::Equal] => Self(&[Ordering::Greater, Ordering::Equal]),
+            [Ordering::Greater, Ordering::Equal] => Self(&[Ordering::Less, Ordering::Equal]),
+            _ => self,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Token {
+    negated: bool,
+    /// The term without `-` and quotes
+    raw: String,
+    /// `(field, operator, value)`
+    scoped: Option<(String, Op, String)>,
+}
+
+/// Splits by whitespace outside of double quotes, with the byte ranges
+fn tokenize(input: &str) -> Vec<(Token, Range<usize>)> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut end = start;
+        let mut quoted = false;
+        while let Some((i, c)) = chars.next_if(|&(_, c)| quoted || !c.is_whitespace()) {
+            quoted ^= c == '"';
+            end = i + c.len_utf8();
+        }
+        tokens.push((token(&input[start..end]), start..end));
+    }
+
+    tokens
+}
+
+fn token(s: &str) -> Token {
+    let (negated, s) = match s.strip_prefix('-') {
+        Some(rest) if !rest.is_empty() => (true, rest),
+        _ => (false, s),
+    };
+    let unquote = |s: &str| s.replace('"', "");
+
+    let name_len = s
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(s.len());
+    let (name, rest) = s.split_at(name_len);
+    let op = [
+        (">=", Op::Ge),
+        ("<=", Op::Le),
+        (":", Op::Contains),
+        ("=", Op::Eq),
+        ("<", Op::Lt),
+        (">", Op::Gt),
+    ]
+    .into_iter()
+    .find_map(|(symbol, op)| rest.strip_prefix(symbol).map(|value| (op, value)));
+
+    let scoped = match op {
+        Some((op, value)) if !name.is_empty() && !value.is_empty() => {
+            Some((name.to_string(), op, unquote(value)))
+        }
+        _ => None,
+    };
+
+    Token {
+        negated,
+        raw: unquote(s),
+        scoped,
+    }
+}
+
+/// `10M`, `1.5GiB`, `512k`, `100` (bytes)
+fn parse_size(s: &str) -> Option<u64> {
+    let (number, unit) = split_number(s)?;
+    let unit = unit.to_ascii_lowercase();
+    let unit = unit
+        .strip_suffix("ib")
+        .or_else(|| unit.strip_suffix('b'))
+        .unwrap_or(&unit);
+    let exp = ["", "k", "m", "g", "t"].iter().position(|u| *u == unit)?;
+
+    Some((number * 1024f64.powi(exp as i32)) as u64)
+}
+
+/// `30s`, `15m`, `3h`, `7d`, `2w`, `1y`
+fn parse_age(s: &str) -> Option<Duration> {
+    let (number, unit) = split_number(s)?;
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        "y" => 365 * 24 * 60 * 60,
+        _ => return None,
+    };
+
+    Duration::try_from_secs_f64(number * secs as f64).ok()
+}
+
+fn split_number(s: &str) -> Option<(f64, &str)> {
+    let len = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let number: f64 = s[..len].parse().ok()?;
+    (number >= 0.0).then_some((number, &s[len..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Item {
+        name: String,
+        path: String,
+        tags: Vec<String>,
+        size: u64,
+        age: Duration,
+        rating: f64,
+    }
+
+    fn schema() -> Schema<Item> {
+        Schema::new()
+            .text("name", |i: &Item| i.name.clone())
+            .text("path", |i: &Item| i.path.clone())
+            .tags("tag", |i: &Item| i.tags.clone())
+            .size("size", |i: &Item| i.size)
+            .time("modified", |i: &Item| SystemTime::now() - i.age)
+            .number("rating", |i: &Item| i.rating)
+    }
+
+    fn item() -> Item {
+        Item {
+            name: "Report.pdf".into(),
+            path: "/home/me/work".into(),
+            tags: vec!["work".into(), "Q3".into()],
+            size: 15 << 20,
+            age: Duration::from_secs(3 * 24 * 60 * 60),
+            rating: 4.5,
+        }
+    }
+
+    fn matches(query: &str) -> bool {
+        let schema = schema();
+        let query = schema.parse(query);
+        assert_eq!(query.diagnostics(), []);
+        query.matches(&item())
+    }
+
+    #[test]
+    fn tokens() {
+        let tokens = tokenize(r#" name:"a b"  -tag:x  size>=10M -  a<"#);
+        let spans: Vec<_> = tokens.iter().map(|(_, span)| span.clone()).collect();
+        assert_eq!(spans, [1..11, 13..19, 21..30, 31..32, 34..36]);
+
+        assert_eq!(
+            tokens[0].0.scoped,
+            Some(("name".into(), Op::Contains, "a b".into()))
+        );
+        assert!(tokens[1].0.negated);
+        assert_eq!(
+            tokens[2].0.scoped,
+            Some(("size".into(), Op::Ge, "10M".into()))
+        );
+        // 単独の`-`や値のない`a<`は単語
+        assert_eq!(
+            (tokens[3].0.negated, tokens[3].0.scoped.is_none()),
+            (false, true)
+        );
+        assert_eq!(tokens[4].0.scoped, None);
+    }
+
+    #[test]
+    fn text_and_tags() {
+        assert!(matches("report"));
+        assert!(matches("work"));
+        assert!(!matches("Work"));
+        assert!(matches("name:report"));
+        assert!(!matches("name:work"));
+        assert!(matches("name=report.pdf"));
+        assert!(!matches("name=report"));
+        assert!(matches("tag:q3 tag=work"));
+        assert!(!matches("tag:wor"));
+        assert!(matches("-tag:home report"));
+        assert!(!matches("-name:report"));
+
+        let schema = schema().default_fields(["name"]);
+        assert!(!schema.parse("work").matches(&item()));
+    }
+
+    #[test]
+    fn default_fields() {
+        let schema = schema().default_fields(["name", "size", "owner"]);
+        let query = schema.parse("size>1M report tag:work");
+        let messages: Vec<_> = query
+            .diagnostics()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "default field `size` (size) is not a text field",
+                "unknown default field `owner`",
+            ]
+        );
+        assert_eq!(query.diagnostics()[0].span, 8..14);
+        assert!(query.matches(&item()));
+
+        assert_eq!(schema.parse("tag:work").diagnostics(), []);
+    }
+
+    #[test]
+    fn comparisons() {
+        assert!(matches("size>10M size<=15MiB size>=15728640"));
+        assert!(!matches("size>15m"));
+        assert!(matches("modified<7d modified>1d"));
+        assert!(!matches("modified<2d"));
+        assert!(!matches("-modified<1w"));
+        assert!(matches("rating>=4.5 rating:4.5 rating<5"));
+        assert!(!matches("rating>4.5"));
+    }
+
+    #[test]
+    fn diagnostics() {
+        let schema = schema();
+        let query = schema.parse("http://example.com size>huge tag>3 modified:1d rating<x");
+        let messages: Vec<_> = query
+            .diagnostics()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "unknown field `http`",
+                "invalid size `huge`",
+                "`tag` (tags) can't be used with `>`",
+                "`modified` (time) can't be used with `:`",
+                "invalid number `x`",
+            ]
+        );
+        assert_eq!(query.diagnostics()[1].span, 19..28);
+
+        // 未知のfieldは単語として扱い、他は無視する
+        assert!(!query.matches(&item()));
+        assert!(schema.parse("size>huge").matches(&item()));
+        assert!(schema.parse("work:report").matches(&Item {
+            name: "work:report".into(),
+            ..item()
+        }));
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(parse_size("100"), Some(100));
+        assert_eq!(parse_size("1.5k"), Some(1536));
+        assert_eq!(parse_size("2GB"), Some(2 << 30));
+        assert_eq!(parse_size("1x"), None);
+        assert_eq!(
+            parse_age("2w"),
+            Some(Duration::from_secs(14 * 24 * 60 * 60))
+        );
+        assert_eq!(parse_age("7"), None);
+        assert_eq!(parse_age("-1d"), None);
+    }
+}
@@ -0,0 +1,194 @@
+//! Named factories of extensions, so that a launcher can be assembled from names at runtime.
+//!
+//! Extension crates can provide a [`Plugin`] that registers their factories,
+//! and the launcher is built with [`Launcher::add_named_source`](crate::launcher::Launcher::add_named_source)
+//! and friends (or from a config file with the `config` feature).
+//!
+//! ```
+//! use ltrait::{
+//!     filter::ClosureFilter,
+//!     registry::{Registry, toml::Table},
+//!     source::from_iter,
+//! };
+//!
+//! fn numbers(registry: Registry<i32>) -> Registry<i32> {
+//!     registry
+//!         .register_source("numbers", |options| {
+//!             let count = options.get("count").and_then(|c| c.as_integer()).unwrap_or(10);
+//!             Ok(from_iter(0..count as i32))
+//!         })
+//!         .register_filter("even", |_| {
+//!             Ok(Box::new(ClosureFilter::new(|&x: &i32, _| x % 2 == 0)))
+//!         })
+//! }
+//!
+//! let registry = Registry::default().plugin(numbers);
+//! assert!(registry.filter("even", &Table::new()).is_ok());
+//! assert!(registry.filter("odd", &Table::new()).is_err());
+//! ```
+
+use color_eyre::eyre::{Context as _, Result, eyre};
+use std::collections::BTreeMap;
+
+pub use toml;
+use toml::Table;
+
+use crate::action::Action;
+use crate::filter::Filter;
+use crate::generator::Generator;
+use crate::scorer::Scorer;
+use crate::sorter::Sorter;
+use crate::source::Source;
+
+type Factory<T> = Box<dyn Fn(&Table) -> Result<T> + Send + Sync>;
+
+pub type FilterT<Cushion> = Box<dyn Filter<Context = Cushion>>;
+pub type SorterT<Cushion> = Box<dyn Sorter<Context = Cushion>>;
+pub type ScorerT<Cushion> = Box<dyn Scorer<Context = Cushion>>;
+pub type GenT<Cushion> = Box<dyn Generator<Item = Cushion>>;
+pub type ActionT<Cushion> = Box<dyn Action<Context = Cushion>>;
+
+struct Factories<T> {
+    kind: &'static str,
+    map: BTreeMap<String, Factory<T>>,
+}
+
+impl<T> Factories<T> {
+    fn new(kind: &'static str) -> Self {
+        Self {
+            kind,
+            map: BTreeMap::new(),
+        }
+    }
+
+    fn create(&self, name: &str, options: &Table) -> Result<T> {
+        let factory = self
+            .map
+            .get(name)
+            .ok_or_else(|| eyre!("unknown {} `{name}`", self.kind))?;
+
+        factory(options).wrap_err_with(|| format!("failed to create {} `{name}`", self.kind))
+    }
+}
+
+/// Maps names to factories of extensions for a `Cushion` type.
+///
+/// A factory takes the options of the extension as a TOML table.
+/// Use `options.clone().try_into::<T>()` to deserialize them into your own type.
+///
+/// Registering a name twice overrides the older factory.
+pub struct Registry<Cushion> {
+    sources: Factories<Source<Cushion>>,
+    filters: Factories<FilterT<Cushion>>,
+    sorters: Factories<SorterT<Cushion>>,
+    scorers: Factories<ScorerT<Cushion>>,
+    generators: Factories<GenT<Cushion>>,
+    actions: Factories<ActionT<Cushion>>,
+}
+
+impl<Cushion> Default for Registry<Cushion> {
+    fn default() -> Self {
+        Self {
+            sources: Factories::new("source"),
+            filters: Factories::new("filter"),
+            sorters: Factories::new("sorter"),
+            scorers: Factories::new("scorer"),
+            generators: Factories::new("generator"),
+            actions: Factories::new("action"),
+        }
+    }
+}
+
+/// A set of factories provided by an extension crate.
+///
+/// It is implemented for `FnOnce(Registry<Cushion>) -> Registry<Cushion>`.
+pub trait Plugin<Cushion> {
+    fn register(self, registry: Registry<Cushion>) -> Registry<Cushion>;
+}
+
+impl<Cushion, F> Plugin<Cushion> for F
+where
+    F: FnOnce(Registry<Cushion>) -> Registry<Cushion>,
+{
+    fn register(self, registry: Registry<Cushion>) -> Registry<Cushion> {
+        self(registry)
+    }
+}
+
+impl<Cushion> Registry<Cushion> {
+    pub fn plugin(self, plugin: impl Plugin<Cushion>) -> Self {
+        plugin.register(self)
+    }
+
+    pub fn register_source<F>(mut self, name: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn(&Table) -> Result<Source<Cushion>> + Send + Sync + 'static,
+    {
+        self.sources.map.insert(name.into(), Box::new(factory));
+        self
+    }
+
+    pub fn register_filter<F>(mut self, name: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn(&Table) -> Result<FilterT<Cushion>> + Send + Sync + 'static,
+    {
+        self.filters.map.insert(name.into(), Box::new(factory));
+        self
+    }
+
+    pub fn register_sorter<F>(mut self, name: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn(&Table) -> Result<SorterT<Cushion>> + Send + Sync + 'static,
+    {
+        self.sorters.map.insert(name.into(), Box::new(factory));
+        self
+    }
+
+    pub fn register_scorer<F>(mut self, name: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn(&Table) -> Result<ScorerT<Cushion>> + Send + Sync + 'static,
+    {
+        self.scorers.map.insert(name.into(), Box::new(factory));
+        self
+    }
+
+    pub fn register_generator<F>(mut self, name: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn(&Table) -> Result<GenT<Cushion>> + Send + Sync + 'static,
+    {
+        self.generators.map.insert(name.into(), Box::new(factory));
+        self
+    }
+
+    pub fn register_action<F>(mut self, name: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn(&Table) -> Result<ActionT<Cushion>> + Send + Sync + 'static,
+    {
+        self.actions.map.insert(name.into(), Box::new(factory));
+        self
+    }
+
+    pub fn source(&self, name: &str, options: &Table) -> Result<Source<Cushion>> {
+        self.sources.create(name, options)
+    }
+
+    pub fn filter(&self, name: &str, options: &Table) -> Result<FilterT<Cushion>> {
+        self.filters.create(name, options)
+    }
+
+    pub fn sorter(&self, name: &str, options: &Table) -> Result<SorterT<Cushion>> {
+        self.sorters.create(name, options)
+    }
+
+    pub fn scorer(&self, name: &str, options: &Table) -> Result<ScorerT<Cushion>> {
+        self.scorers.create(name, options)
+    }
+
+    pub fn generator(&self, name: &str, options: &Table) -> Result<GenT<Cushion>> {
+        self.generators.create(name, options)
+    }
+
+    pub fn action(&self, name: &str, options: &Table) -> Result<ActionT<Cushion>> {
+        self.actions.create(name, options)
+    }
+}
@@ -0,0 +1,109 @@
+use std::marker::PhantomData;
+
+/// Scorer gives each item a numeric score for the current input.
+///
+/// Unlike [`Sorter`](crate::sorter::Sorter), which compares two items, a Scorer is evaluated only
+/// once per item. The launcher multiplies each score by the weight given in
+/// [`Launcher::add_scorer`](crate::launcher::Launcher::add_scorer) and sorts by the total (higher first),
+/// so that signals such as a fuzzy score, frecency and source priority can be blended.
+pub trait Scorer: Send {
+    type Context;
+
+    fn score(&self, ctx: &Self::Context, input: &str) -> f64;
+
+    /// Scores all the items of a batch at once.
+    ///
+    /// Override this when each call has a fixed cost (e.g. IPC). The result must have the same length as `ctxs`.
+    fn score_batch(&self, ctxs: &[&Self::Context], input: &str) -> Vec<f64> {
+        ctxs.iter().map(|ctx| self.score(ctx, input)).collect()
+    }
+}
+
+impl<T> Scorer for Box<T>
+where
+    T: Scorer + ?Sized,
+{
+    type Context = T::Context;
+
+    fn score(&self, ctx: &Self::Context, input: &str) -> f64 {
+        (**self).score(ctx, input)
+    }
+
+    fn score_batch(&self, ctxs: &[&Self::Context], input: &str) -> Vec<f64> {
+        (**self).score_batch(ctxs, input)
+    }
+}
+
+pub struct ClosureScorer<Context, F>(F, PhantomData<Context>)
+where
+    F: Fn(&Context, &str) -> f64;
+
+impl<Context, F> ClosureScorer<Context, F>
+where
+    F: Fn(&Context, &str) -> f64,
+{
+    pub fn new(f: F) -> Self {
+        Self(f, PhantomData)
+    }
+}
+
+impl<Context, F> Scorer for ClosureScorer<Context, F>
+where
+    F: Fn(&Context, &str) -> f64 + Send,
+    Context: Sync + Send,
+{
+    type Context = Context;
+
+    fn score(&self, ctx: &Self::Context, input: &str) -> f64 {
+        (self.0)(ctx, input)
+    }
+}
+
+pub struct ScorerWrapper<ScorerContext, ScorerT, F, Cushion>
+where
+    F: Fn(&Cushion) -> ScorerContext + Send,
+    ScorerT: Scorer<Context = ScorerContext>,
+{
+    f: F,
+    scorer: ScorerT,
+
+    _marker: PhantomData<(ScorerContext, Cushion)>,
+}
+
+impl<ScorerContext, ScorerT, F, Cushion> Scorer
+    for ScorerWrapper<ScorerContext, ScorerT, F, Cushion>
+where
+    F: Fn(&Cushion) -> ScorerContext + Send,
+    ScorerT: Scorer<Context = ScorerContext>,
+    ScorerContext: Sync + Send,
+    Cushion: Send,
+{
+    type Context = Cushion;
+
+    fn score(&self, ctx: &Self::Context, input: &str) -> f64 {
+        self.scorer.score(&(self.f)(ctx), input)
+    }
+
+    fn score_batch(&self, ctxs: &[&Self::Context], input: &str) -> Vec<f64> {
+        let ctxs: Vec<_> = ctxs.iter().map(|ctx| (self.f)(ctx)).collect();
+        self.scorer
+            .score_batch(&ctxs.iter().collect::<Vec<_>>(), input)
+    }
+}
+
+impl<ScorerContext, ScorerT, F, Cushion> ScorerWrapper<ScorerContext, ScorerT, F, Cushion>
+where
+    F: Fn(&Cushion) -> ScorerContext + Send,
+    ScorerT: Scorer<Context = ScorerContext>,
+    ScorerContext: Sync,
+    Cushion: Send,
+{
+    pub fn new(scorer: ScorerT, transformer: F) -> Self {
+        Self {
+            f: transformer,
+            scorer,
+
+            _marker: PhantomData,
+        }
+    }
+}
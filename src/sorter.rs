@@ -4,6 +4,28 @@ pub trait Sorter: Send {
     type Context;
 
     fn compare(&self, lhs: &Self::Context, rhs: &Self::Context, input: &str) -> std::cmp::Ordering;
+
+    /// Called with the items of a batch before they are compared.
+    ///
+    /// Override this to compute the sort keys of all the items at once when each call has a fixed cost (e.g. IPC).
+    fn prepare_batch(&self, ctxs: &[&Self::Context], input: &str) {
+        let _ = (ctxs, input);
+    }
+}
+
+impl<T> Sorter for Box<T>
+where
+    T: Sorter + ?Sized,
+{
+    type Context = T::Context;
+
+    fn compare(&self, lhs: &Self::Context, rhs: &Self::Context, input: &str) -> std::cmp::Ordering {
+        (**self).compare(lhs, rhs, input)
+    }
+
+    fn prepare_batch(&self, ctxs: &[&Self::Context], input: &str) {
+        (**self).prepare_batch(ctxs, input)
+    }
 }
 
 pub struct ClosureSorter<Context, F>(F, PhantomData<Context>)
@@ -56,6 +78,12 @@ where
     fn compare(&self, lhs: &Self::Context, rhs: &Self::Context, input: &str) -> std::cmp::Ordering {
         (self.sorter).compare(&(self.f)(lhs), &(self.f)(rhs), input)
     }
+
+    fn prepare_batch(&self, ctxs: &[&Self::Context], input: &str) {
+        let ctxs: Vec<_> = ctxs.iter().map(|ctx| (self.f)(ctx)).collect();
+        self.sorter
+            .prepare_batch(&ctxs.iter().collect::<Vec<_>>(), input)
+    }
 }
 
 impl<SorterContext, SorterT, F, Cushion> SorterWrapper<SorterContext, SorterT, F, Cushion>
@@ -1,5 +1,9 @@
+use color_eyre::Result;
 use std::pin::Pin;
 
+#[cfg(feature = "command")]
+pub mod command;
+
 pub type Source<T> = Pin<Box<dyn tokio_stream::Stream<Item = T> + Send>>;
 
 pub fn from_iter<T, Iter>(
@@ -23,3 +27,51 @@ where
 
     Box::pin(source.map(f))
 }
+
+pub fn transform_try_source<Cushion, SourceContext, F>(
+    source: Source<Result<SourceContext>>,
+    f: F,
+) -> Source<Result<Cushion>>
+where
+    SourceContext: 'static,
+    F: Fn(SourceContext) -> Cushion + Send + 'static,
+{
+    use tokio_stream::StreamExt as _;
+
+    Box::pin(source.map(move |item| item.map(&f)))
+}
+
+/// An event emitted by a [`LiveSource`]. `Id` identifies the item in the source.
+pub enum SourceEvent<Id, T> {
+    /// Adds the item. If an item with the same id exists, it is replaced.
+    Insert(Id, T),
+    /// Replaces the item with the same id. If there is no such item, it is added.
+    Update(Id, T),
+    /// Removes the item with the id.
+    Remove(Id),
+}
+
+/// Unlike [`Source`], which only appends items, a live source can replace and remove the items it emitted,
+/// so it can be used for things that come and go (processes, windows, ...).
+///
+/// The events are applied to the items each time the batcher prepares a batch, so the UI needs to keep
+/// calling `prepare` and `merge` to reflect them.
+pub type LiveSource<Id, T> = Pin<Box<dyn tokio_stream::Stream<Item = SourceEvent<Id, T>> + Send>>;
+
+pub fn transform_live_source<Id, Cushion, SourceContext, F>(
+    source: LiveSource<Id, SourceContext>,
+    f: F,
+) -> LiveSource<Id, Cushion>
+where
+    Id: 'static,
+    SourceContext: 'static,
+    F: Fn(SourceContext) -> Cushion + Send + 'static,
+{
+    use tokio_stream::StreamExt as _;
+
+    Box::pin(source.map(move |event| match event {
+        SourceEvent::Insert(id, c) => SourceEvent::Insert(id, f(c)),
+        SourceEvent::Update(id, c) => SourceEvent::Update(id, f(c)),
+        SourceEvent::Remove(id) => SourceEvent::Remove(id),
+    }))
+}
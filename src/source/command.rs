@@ -0,0 +1,193 @@
+//! A source that spawns a process and streams the records of its stdout.
+//!
+//! ```no_run
+//! # fn f() -> ltrait::color_eyre::Result<()> {
+//! use ltrait::source::command::Command;
+//!
+//! let files = Command::new("fd").args(["--type", "f"]).spawn()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Since the source can fail, add it by [`Launcher::add_try_source`](crate::launcher::Launcher::add_try_source).
+
+use color_eyre::eyre::{OptionExt, Result, eyre};
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::Stdio;
+
+use tokio::io::{AsyncBufReadExt, BufReader, Split};
+use tokio::process::{Child, ChildStdout};
+
+use super::Source;
+
+/// A builder of the command source, similar to [`std::process::Command`].
+#[derive(Debug)]
+pub struct Command {
+    inner: tokio::process::Command,
+    program: String,
+    separator: u8,
+}
+
+impl Command {
+    pub fn new(program: impl AsRef<OsStr>) -> Self {
+        Self {
+            program: program.as_ref().to_string_lossy().into_owned(),
+            inner: tokio::process::Command::new(program),
+            separator: b'\n',
+        }
+    }
+
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.inner.arg(arg);
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.inner.args(args);
+        self
+    }
+
+    pub fn env(mut self, key: impl AsRef<OsStr>, val: impl AsRef<OsStr>) -> Self {
+        self.inner.env(key, val);
+        self
+    }
+
+    pub fn envs<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.inner.envs(vars);
+        self
+    }
+
+    pub fn current_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.inner.current_dir(dir);
+        self
+    }
+
+    /// Splits the output by NUL instead of newlines (e.g. for `fd -0` or `git ls-files -z`).
+    pub fn nul_separated(mut self) -> Self {
+        self.separator = b'\0';
+        self
+    }
+
+    /// Spawns the process and returns a source of its records.
+    ///
+    /// Records are yielded as they arrive, without the separator (and a trailing `\r` for lines).
+    /// If the process exits with a non-zero status, an error is yielded at the end.
+    /// The process is killed when the source is dropped, i.e. when the launcher finishes.
+    pub fn spawn(mut self) -> Result<Source<Result<String>>> {
+        let mut child = self
+            .inner
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_eyre("failed to get stdout of the child process")?;
+
+        let state = State {
+            records: BufReader::new(stdout).split(self.separator),
+            child,
+            program: self.program,
+            separator: self.separator,
+        };
+
+        Ok(Box::pin(futures::stream::unfold(
+            Some(state),
+            |state| async move {
+                let mut state = state?;
+
+                match state.records.next_segment().await {
+                    Ok(Some(record)) => {
+                        let record = state.decode(record);
+                        Some((Ok(record), Some(state)))
+                    }
+                    Ok(None) => match state.child.wait().await {
+                        Ok(status) if status.success() => None,
+                        Ok(status) => {
+                            Some((Err(eyre!("`{}` exited with {status}", state.program)), None))
+                        }
+                        Err(e) => Some((Err(e.into()), None)),
+                    },
+                    Err(e) => Some((Err(e.into()), None)),
+                }
+            },
+        )))
+    }
+}
+
+struct State {
+    records: Split<BufReader<ChildStdout>>,
+    // dropされたらkillされる
+    child: Child,
+    program: String,
+    separator: u8,
+}
+
+impl State {
+    fn decode(&self, mut record: Vec<u8>) -> String {
+        if self.separator == b'\n' && record.last() == Some(&b'\r') {
+            record.pop();
+        }
+
+        String::from_utf8(record)
+            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt as _;
+
+    #[tokio::test]
+    async fn lines() -> Result<()> {
+        let source = Command::new("sh")
+            .args(["-c", "printf 'a\\r\\nb\\n'"])
+            .spawn()?;
+
+        let records: Vec<_> = source.collect::<Result<_>>().await?;
+        assert_eq!(records, ["a", "b"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn nul_separated() -> Result<()> {
+        let source = Command::new("printf")
+            .arg("a b\\0c")
+            .nul_separated()
+            .spawn()?;
+
+        let records: Vec<_> = source.collect::<Result<_>>().await?;
+        assert_eq!(records, ["a b", "c"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn exit_status() -> Result<()> {
+        let mut source = Command::new("sh")
+            .args(["-c", "echo $FOO; exit 3"])
+            .env("FOO", "foo")
+            .spawn()?;
+
+        assert_eq!(source.next().await.transpose()?, Some("foo".into()));
+        assert!(source.next().await.unwrap().is_err());
+        assert!(source.next().await.is_none());
+
+        Ok(())
+    }
+}
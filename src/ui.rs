@@ -1,12 +1,35 @@
 use color_eyre::Result;
 
+#[cfg(feature = "driver")]
+pub mod driver;
+#[cfg(feature = "input")]
+pub mod input;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod viewport;
+
+pub use viewport::Viewport;
+
+/// What the user chose in the UI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection<Cushion> {
+    /// An item, given to the actions. Get it by [`Batcher::compute_cushion`](crate::launcher::batcher::Batcher::compute_cushion).
+    Item(Cushion),
+    /// The raw input, given to the fallback actions (e.g. when nothing matches).
+    /// See [`Launcher::add_raw_fallback_action`](crate::launcher::Launcher::add_raw_fallback_action).
+    Query(String),
+}
+
 pub trait UI<Cushion: Send + Sync + 'static> {
     type Context;
 
+    /// Returns None if the user closed the UI without choosing anything
     fn run(
         &self,
         batcher: crate::launcher::batcher::Batcher<Cushion, Self::Context>,
-    ) -> impl std::future::Future<Output = Result<Option<Cushion>>> + Send;
+    ) -> impl std::future::Future<Output = Result<Option<Selection<Cushion>>>> + Send;
 }
 
 #[derive(Debug, Clone)]
@@ -51,6 +74,11 @@ impl<T> Buffer<T> {
         &mut self.vec
     }
 
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        &self.vec
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.vec.len()
@@ -70,8 +98,12 @@ impl<T> Buffer<T> {
     #[allow(clippy::should_implement_trait)]
     #[inline]
     pub fn next(&self, pos: &mut Position) -> Option<&T> {
-        pos.0 += 1;
-        self.vec.get(pos.0 - 1)
+        // 末尾で進めてしまうとその後にpushされたものを飛ばしてしまう
+        let item = self.vec.get(pos.0);
+        if item.is_some() {
+            pos.0 += 1;
+        }
+        item
     }
 
     #[inline]
@@ -104,6 +136,10 @@ mod tests {
         assert_eq!(buf.next(&mut pos), Some((2u32, 2)).as_ref());
         assert_eq!(buf.next(&mut pos), None);
 
+        let mut buf = buf;
+        buf.push((3, 3));
+        assert_eq!(buf.next(&mut pos), Some((3u32, 3)).as_ref());
+
         Ok(())
     }
 }
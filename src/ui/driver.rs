@@ -0,0 +1,289 @@
+//! Runs the batcher in a background task, so that a UI only sends inputs and reacts to the updates.
+//!
+//! ```no_run
+//! use ltrait::{
+//!     color_eyre::Result,
+//!     launcher::batcher::Batcher,
+//!     tokio_stream::StreamExt as _,
+//!     ui::Selection,
+//!     ui::driver::{Driver, Rows},
+//! };
+//!
+//! async fn run(batcher: Batcher<String, String>) -> Result<Option<Selection<String>>> {
+//!     let mut driver = Driver::spawn(batcher);
+//!     let mut rows = Rows::default();
+//!
+//!     driver.input("foo");
+//!     while let Some(update) = driver.next().await {
+//!         rows.apply(update);
+//!         // draw rows.as_slice() and rows.error() here
+//!         if rows.is_done() {
+//!             break;
+//!         }
+//!     }
+//!
+//!     let selected = rows.as_slice().first().map(|(_, id)| *id);
+//!     let batcher = driver.finish().await?;
+//!     selected
+//!         .map(|id| batcher.compute_cushion(id).map(Selection::Item))
+//!         .transpose()
+//! }
+//! ```
+
+use color_eyre::{Report, Result};
+use std::collections::BTreeSet;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tokio::task::JoinHandle;
+
+use crate::launcher::batcher::{Batcher, Changes};
+use crate::ui::Buffer;
+
+/// How often the live sources are polled after all the batches are done
+const LIVE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A change of the results. Apply them in order, e.g. with [`Rows::apply`].
+#[derive(Debug)]
+pub enum Update<T> {
+    /// A new input has been accepted. All the rows are cleared.
+    Reset,
+    /// A batch has been merged.
+    Changed {
+        /// ids of the rows removed (e.g. replaced by a live source, or pushed out by the limit)
+        removed: Vec<usize>,
+        /// `(index, context, id)` of the rows inserted, in ascending order of the index.
+        /// The index is the position after the removal and all the insertions.
+        inserted: Vec<(usize, T, usize)>,
+    },
+    /// All the batches for the input are done.
+    /// This can be followed by [`Update::Changed`] if a live source changes.
+    Done {
+        /// See [`Batcher::is_truncated`]
+        truncated: bool,
+    },
+    /// An error from [`Batcher::merge`]. The driver keeps running.
+    /// [`Rows::apply`] keeps it as [`Rows::error`].
+    Error(Report),
+}
+
+enum Command {
+    Input(String),
+    ExtendLimit(usize),
+}
+
+/// Drives a [`Batcher`] in a tokio task. It is a [`Stream`](tokio_stream::Stream) of [`Update`]s.
+pub struct Driver<Cushion, T> {
+    commands: UnboundedSender<Command>,
+    updates: UnboundedReceiver<Update<T>>,
+    task: JoinHandle<Batcher<Cushion, T>>,
+}
+
+impl<Cushion, T> Driver<Cushion, T>
+where
+    Cushion: Send + 'static,
+    T: Clone + Send + 'static,
+{
+    /// Starts processing the empty input
+    pub fn spawn(batcher: Batcher<Cushion, T>) -> Self {
+        let (commands, commands_rx) = unbounded_channel();
+        let (updates_tx, updates) = unbounded_channel();
+
+        Self {
+            commands,
+            updates,
+            task: tokio::spawn(drive(batcher, commands_rx, updates_tx)),
+        }
+    }
+
+    /// Starts a new input. The batches of the previous input are abandoned.
+    pub fn input(&self, input: &str) {
+        // taskが終わっているならfinishで分かる
+        let _ = self.commands.send(Command::Input(input.into()));
+    }
+
+    /// See [`Batcher::extend_limit`]
+    pub fn extend_limit(&self, additional: usize) {
+        let _ = self.commands.send(Command::ExtendLimit(additional));
+    }
+
+    /// Stops the task and returns the batcher, e.g. to call [`Batcher::compute_cushion`].
+    pub async fn finish(self) -> Result<Batcher<Cushion, T>> {
+        drop(self.commands);
+        Ok(self.task.await?)
+    }
+}
+
+impl<Cushion, T> tokio_stream::Stream for Driver<Cushion, T> {
+    type Item = Update<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.updates.poll_recv(cx)
+    }
+}
+
+async fn drive<Cushion, T>(
+    mut batcher: Batcher<Cushion, T>,
+    mut commands: UnboundedReceiver<Command>,
+    updates: UnboundedSender<Update<T>>,
+) -> Batcher<Cushion, T>
+where
+    Cushion: Send,
+    T: Clone,
+{
+    let mut buf = Buffer::default();
+    let mut more = true;
+    let _ = updates.send(Update::Reset);
+
+    loop {
+        let command = if more {
+            // 溜まっているものだけ見る
+            match commands.try_recv() {
+                Ok(command) => Some(command),
+                Err(tokio::sync::mpsc::error::TryRecvError::Empty) => None,
+                Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => break,
+            }
+        } else if batcher.is_live() {
+            tokio::select! {
+                command = commands.recv() => match command {
+                    Some(command) => Some(command),
+                    None => break,
+                },
+                _ = tokio::time::sleep(LIVE_INTERVAL) => None,
+            }
+        } else {
+            match commands.recv().await {
+                Some(command) => Some(command),
+                None => break,
+            }
+        };
+
+        if let Some(command) = command {
+            match command {
+                Command::Input(input) => batcher.input(&mut buf, &input),
+                Command::ExtendLimit(additional) => batcher.extend_limit(&mut buf, additional),
+            }
+            more = true;
+            let _ = updates.send(Update::Reset);
+            continue;
+        }
+
+        let mut changes = Changes::default();
+        let from = batcher.prepare().await;
+        let result = batcher.merge_with_changes(&mut buf, from, Some(&mut changes));
+        if !changes.removed.is_empty() || !changes.inserted.is_empty() {
+            let rows = buf.as_slice();
+            let inserted = changes
+                .inserted
+                .into_iter()
+                .map(|i| (i, rows[i].0.clone(), rows[i].1))
+                .collect();
+            let _ = updates.send(Update::Changed {
+                removed: changes.removed,
+                inserted,
+            });
+        }
+
+        match result {
+            Ok(m) => {
+                let was_more = more;
+                more = m;
+
+                if was_more && !more {
+                    let _ = updates.send(Update::Done {
+                        truncated: batcher.is_truncated(),
+                    });
+                }
+            }
+            Err(e) => {
+                let _ = updates.send(Update::Error(e));
+            }
+        }
+    }
+
+    batcher
+}
+
+/// The rows built from [`Update`]s
+#[derive(Debug, Clone)]
+pub struct Rows<T> {
+    rows: Vec<(T, usize)>,
+    done: bool,
+    truncated: bool,
+    error: Option<String>,
+}
+
+impl<T> Default for Rows<T> {
+    fn default() -> Self {
+        Self {
+            rows: vec![],
+            done: false,
+            truncated: false,
+            error: None,
+        }
+    }
+}
+
+impl<T> Rows<T> {
+    /// Applies the update. [`Update::Error`] doesn't stop the rows, it is kept as [`Rows::error`].
+    pub fn apply(&mut self, update: Update<T>) {
+        match update {
+            Update::Reset => *self = Self::default(),
+            Update::Changed { removed, inserted } => {
+                let removed: BTreeSet<_> = removed.into_iter().collect();
+                let mut old = std::mem::take(&mut self.rows)
+                    .into_iter()
+                    .filter(|(_, id)| !removed.contains(id));
+                let mut inserted = inserted.into_iter().peekable();
+
+                // insertedのindexは最終的な位置なので、その間を古い行で埋める
+                loop {
+                    let row = match inserted.next_if(|(i, _, _)| *i == self.rows.len()) {
+                        Some((_, context, id)) => Some((context, id)),
+                        None => old.next(),
+                    };
+                    match row {
+                        Some(row) => self.rows.push(row),
+                        None => break,
+                    }
+                }
+                self.rows
+                    .extend(inserted.map(|(_, context, id)| (context, id)));
+            }
+            Update::Done { truncated } => {
+                self.done = true;
+                self.truncated = truncated;
+            }
+            Update::Error(e) => self.error = Some(format!("{e:#}")),
+        }
+    }
+
+    /// `(context, id)` of the rows
+    pub fn as_slice(&self) -> &[(T, usize)] {
+        &self.rows
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Whether all the batches for the current input are done
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// The last error for the current input, e.g. to show in the status line
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
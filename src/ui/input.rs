@@ -0,0 +1,311 @@
+//! The query line of a UI, with readline (emacs) style editing.
+//!
+//! The cursor moves by grapheme clusters, so that e.g. `é` written as `e` + a combining accent,
+//! or an emoji with a skin tone, is never split.
+//!
+//! ```
+//! use ltrait::ui::input::{Edit, LineEditor};
+//!
+//! let mut line = LineEditor::default();
+//! line.insert_str("firefox private");
+//! line.apply(Edit::WordLeft);
+//! line.apply(Edit::Insert('-'));
+//! line.apply(Edit::Insert('-'));
+//! assert_eq!(line.text(), "firefox --private");
+//!
+//! line.apply(Edit::KillToStart);
+//! line.apply(Edit::End);
+//! line.apply(Edit::Yank);
+//! assert_eq!(line.text(), "privatefirefox --");
+//! ```
+
+use unicode_segmentation::UnicodeSegmentation as _;
+
+use crate::launcher::batcher::Batcher;
+use crate::ui::Buffer;
+
+/// An editing operation, usually mapped from a key by the UI.
+/// The emacs keys are in the documentation of each variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    Insert(char),
+    /// `C-b`
+    Left,
+    /// `C-f`
+    Right,
+    /// `M-b`
+    WordLeft,
+    /// `M-f`
+    WordRight,
+    /// `C-a`
+    Home,
+    /// `C-e`
+    End,
+    /// `Backspace`, `C-h`
+    Backspace,
+    /// `Delete`, `C-d`
+    Delete,
+    /// `C-w`, `M-Backspace`. The deleted text can be yanked.
+    DeleteWordBackward,
+    /// `M-d`. The deleted text can be yanked.
+    DeleteWordForward,
+    /// `C-k`. The deleted text can be yanked.
+    KillToEnd,
+    /// `C-u`. The deleted text can be yanked.
+    KillToStart,
+    /// `C-y`. Inserts the last killed text.
+    Yank,
+    /// `C-t`. Swaps the graphemes around the cursor.
+    Transpose,
+    /// Deletes everything. It can't be yanked.
+    Clear,
+}
+
+/// The text and the cursor of the query line
+#[derive(Debug, Clone, Default)]
+pub struct LineEditor {
+    text: String,
+    /// A byte offset on a grapheme boundary
+    cursor: usize,
+    killed: String,
+    /// Whether the text changed after the last [`LineEditor::sync`]
+    dirty: bool,
+}
+
+impl LineEditor {
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        Self {
+            cursor: text.len(),
+            text,
+            killed: String::new(),
+            dirty: true,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The byte offset of the cursor in [`LineEditor::text`]
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// The text before the cursor, e.g. to compute the column of the cursor on a terminal
+    pub fn before_cursor(&self) -> &str {
+        &self.text[..self.cursor]
+    }
+
+    /// Inserts `s` at the cursor
+    pub fn insert_str(&mut self, s: &str) {
+        self.text.insert_str(self.cursor, s);
+        self.cursor += s.len();
+        self.dirty |= !s.is_empty();
+    }
+
+    /// Applies `edit` and returns whether the text changed
+    pub fn apply(&mut self, edit: Edit) -> bool {
+        let before = self.text.clone();
+        let cursor = self.cursor;
+
+        match edit {
+            Edit::Insert(c) => self.insert_str(c.encode_utf8(&mut [0; 4])),
+            Edit::Left => self.cursor = self.prev_boundary(cursor),
+            Edit::Right => self.cursor = self.next_boundary(cursor),
+            Edit::WordLeft => self.cursor = self.word_left(cursor),
+            Edit::WordRight => self.cursor = self.word_right(cursor),
+            Edit::Home => self.cursor = 0,
+            Edit::End => self.cursor = self.text.len(),
+            Edit::Backspace => self.delete(self.prev_boundary(cursor)..cursor, false),
+            Edit::Delete => self.delete(cursor..self.next_boundary(cursor), false),
+            Edit::DeleteWordBackward => self.delete(self.word_left(cursor)..cursor, true),
+            Edit::DeleteWordForward => self.delete(cursor..self.word_right(cursor), true),
+            Edit::KillToEnd => self.delete(cursor..self.text.len(), true),
+            Edit::KillToStart => self.delete(0..cursor, true),
+            Edit::Yank => {
+                let killed = std::mem::take(&mut self.killed);
+                self.insert_str(&killed);
+                self.killed = killed;
+            }
+            Edit::Transpose => self.transpose(),
+            Edit::Clear => {
+                self.dirty |= !self.text.is_empty();
+                self.text.clear();
+                self.cursor = 0;
+            }
+        }
+
+        self.text != before
+    }
+
+    /// Feeds the text to [`Batcher::input`] if it changed after the last call, and returns whether it did.
+    /// The UI starts preparing the batches again when it returns true.
+    pub fn sync<Cushion, T>(
+        &mut self,
+        batcher: &mut Batcher<Cushion, T>,
+        buf: &mut Buffer<(T, usize)>,
+    ) -> bool
+    where
+        Cushion: Send,
+    {
+        let dirty = std::mem::take(&mut self.dirty);
+        if dirty {
+            batcher.input(buf, &self.text);
+        }
+        dirty
+    }
+
+    fn delete(&mut self, range: std::ops::Range<usize>, kill: bool) {
+        if range.is_empty() {
+            return;
+        }
+
+        if kill {
+            self.killed = self.text[range.clone()].to_string();
+        }
+        self.cursor = range.start;
+        self.text.replace_range(range, "");
+        self.dirty = true;
+    }
+
+    fn transpose(&mut self) {
+        // 行末なら直前の2つを入れ替える(readlineと同じ)
+        let end = if self.cursor == self.text.len() {
+            self.cursor
+        } else {
+            self.next_boundary(self.cursor)
+        };
+        let mid = self.prev_boundary(end);
+        let start = self.prev_boundary(mid);
+        if start == mid {
+            return;
+        }
+
+        let swapped = format!("{}{}", &self.text[mid..end], &self.text[start..mid]);
+        self.text.replace_range(start..end, &swapped);
+        self.cursor = end;
+        self.dirty = true;
+    }
+
+    fn prev_boundary(&self, pos: usize) -> usize {
+        self.text[..pos]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self, pos: usize) -> usize {
+        self.text[pos..]
+            .graphemes(true)
+            .next()
+            .map_or(pos, |g| pos + g.len())
+    }
+
+    fn word_left(&self, pos: usize) -> usize {
+        let mut graphemes = self.text[..pos].grapheme_indices(true).rev().peekable();
+
+        // 単語以外を飛ばしてから単語を飛ばす
+        let mut start = pos;
+        for word in [false, true] {
+            while let Some((i, _)) = graphemes.next_if(|(_, g)| is_word(g) == word) {
+                start = i;
+            }
+        }
+        start
+    }
+
+    fn word_right(&self, pos: usize) -> usize {
+        let mut graphemes = self.text[pos..].grapheme_indices(true).peekable();
+
+        let mut end = pos;
+        for word in [false, true] {
+            while let Some((i, g)) = graphemes.next_if(|(_, g)| is_word(g) == word) {
+                end = pos + i + g.len();
+            }
+        }
+        end
+    }
+}
+
+fn is_word(grapheme: &str) -> bool {
+    grapheme
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str, cursor: usize) -> LineEditor {
+        let mut line = LineEditor::new(text);
+        line.cursor = cursor;
+        line
+    }
+
+    #[test]
+    fn graphemes() {
+        // e + combining acute accent
+        let mut line = LineEditor::new("ae\u{301}");
+        line.apply(Edit::Left);
+        assert_eq!(line.before_cursor(), "a");
+
+        line.apply(Edit::Right);
+        assert!(line.apply(Edit::Backspace));
+        assert_eq!(line.text(), "a");
+
+        assert!(!line.apply(Edit::Delete));
+        assert!(!line.apply(Edit::Right));
+    }
+
+    #[test]
+    fn words() {
+        let mut line = LineEditor::new("foo bar-baz  ");
+        line.apply(Edit::WordLeft);
+        assert_eq!(line.before_cursor(), "foo bar-");
+        line.apply(Edit::WordLeft);
+        line.apply(Edit::WordLeft);
+        assert_eq!(line.cursor(), 0);
+        line.apply(Edit::WordLeft);
+        assert_eq!(line.cursor(), 0);
+
+        line.apply(Edit::WordRight);
+        assert_eq!(line.before_cursor(), "foo");
+        line.apply(Edit::DeleteWordForward);
+        assert_eq!(line.text(), "foo-baz  ");
+        line.apply(Edit::End);
+        line.apply(Edit::DeleteWordBackward);
+        assert_eq!(line.text(), "foo-");
+        line.apply(Edit::Home);
+        line.apply(Edit::Yank);
+        assert_eq!(line.text(), "baz  foo-");
+
+        let mut line = editor("  ", 2);
+        line.apply(Edit::WordLeft);
+        assert_eq!(line.cursor(), 0);
+    }
+
+    #[test]
+    fn kill_and_transpose() {
+        let mut line = editor("abcd", 2);
+        line.apply(Edit::KillToEnd);
+        assert_eq!(line.text(), "ab");
+        line.apply(Edit::KillToStart);
+        assert_eq!(line.text(), "");
+        line.apply(Edit::Yank);
+        line.apply(Edit::Yank);
+        assert_eq!(line.text(), "abab");
+
+        let mut line = editor("abc", 1);
+        assert!(line.apply(Edit::Transpose));
+        assert_eq!((line.text(), line.cursor()), ("bac", 2));
+        line.apply(Edit::End);
+        line.apply(Edit::Transpose);
+        assert_eq!(line.text(), "bca");
+
+        let mut line = editor("a", 1);
+        assert!(!line.apply(Edit::Transpose));
+    }
+}
@@ -0,0 +1,386 @@
+//! A UI that lets an external process (e.g. a front-end written in another toolkit) drive the [`Batcher`]
+//! with JSON-RPC 2.0 messages.
+//!
+//! ```no_run
+//! # async fn f() -> ltrait::color_eyre::Result<()> {
+//! use ltrait::{Launcher, source::from_iter, ui::rpc::RpcUI};
+//!
+//! Launcher::default()
+//!     .add_source(from_iter(0..100), std::convert::identity)
+//!     .set_ui(RpcUI::stdio(), |x: &i32| x.to_string())
+//!     .run()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Protocol
+//!
+//! Each message is a JSON object on a line. The front-end sends requests, and the launcher replies to each of them
+//! (except for notifications, i.e. requests without `id`).
+//!
+//! | method         | params                  | result                                          |
+//! | -------------- | ----------------------- | ----------------------------------------------- |
+//! | `input`        | `{"input": "..."}`      | `{"total": 10, "more": true, "truncated": false, "live": false}` |
+//! | `poll`         | none                    | same as `input`                                 |
+//! | `page`         | `{"offset": 0, "count": 10}` | `{"items": [{"id": 3, "context": ...}, ...]}` |
+//! | `extend_limit` | `{"additional": 10}`    | same as `input`                                 |
+//! | `select`       | `{"id": 3}`             | `null`, and the launcher runs the actions and exits |
+//! | `submit`       | none                    | `null`, and the launcher runs the fallback actions on the input and exits |
+//! | `cancel`       | none                    | `null`, and the launcher exits                  |
+//!
+//! - `input` starts a new query and processes the first batch. While `more` is true, call `poll` to process
+//!   the next batches. `total` is the number of items ranked so far.
+//! - `live` is true while some live sources are open. Their changes are only applied on `input` and `poll`,
+//!   so keep calling `poll` (e.g. periodically) after `more` becomes false to get them.
+//! - `page` returns the ranked items, `context` is the `UIContext` serialized with serde.
+//! - `id` of `select` is the one returned by `page`.
+//! - With [`Launcher::keep_open`](crate::launcher::Launcher::keep_open), `select` runs the actions,
+//!   and returns `{"closed": false}` with the results of an empty input ready to `poll`,
+//!   or `{"closed": true}` and the launcher exits if an action closed it. `submit` works in the same way.
+//!
+//! Errors use the standard codes, and `-32000` for the errors from the launcher (e.g. a failed source).
+//! The launcher also exits when the input is closed.
+//!
+//! ```text
+//! --> {"jsonrpc": "2.0", "id": 1, "method": "input", "params": {"input": "4"}}
+//! <-- {"jsonrpc": "2.0", "id": 1, "result": {"total": 19, "more": false, "truncated": false, "live": false}}
+//! --> {"jsonrpc": "2.0", "id": 2, "method": "page", "params": {"offset": 0, "count": 1}}
+//! <-- {"jsonrpc": "2.0", "id": 2, "result": {"items": [{"id": 4, "context": "4"}]}}
+//! --> {"jsonrpc": "2.0", "id": 3, "method": "select", "params": {"id": 4}}
+//! <-- {"jsonrpc": "2.0", "id": 3, "result": null}
+//! ```
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use std::marker::PhantomData;
+
+use tokio::io::{
+    AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines, Stdin, Stdout,
+};
+use tokio::sync::Mutex;
+
+use crate::action::Flow;
+use crate::launcher::batcher::Batcher;
+use crate::ui::{Buffer, Selection, UI};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const LAUNCHER_ERROR: i64 = -32000;
+
+/// A UI driven by JSON-RPC messages, see [the module documentation](self) for the protocol.
+///
+/// `T` is the `UIContext`, which is sent to the front-end.
+pub struct RpcUI<T, R, W> {
+    io: Mutex<(Lines<BufReader<R>>, W)>,
+
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> RpcUI<T, Stdin, Stdout> {
+    /// Talks over stdin and stdout
+    pub fn stdio() -> Self {
+        Self::new(tokio::io::stdin(), tokio::io::stdout())
+    }
+}
+
+impl<T, R, W> RpcUI<T, R, W>
+where
+    R: AsyncRead + Unpin,
+{
+    /// Talks over any pair of streams, e.g. the halves of a socket
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            io: Mutex::new((BufReader::new(reader).lines(), writer)),
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// None for a notification, and `Some(None)` for `"id": null`
+    #[serde(default, deserialize_with = "present")]
+    id: Option<Option<Value>>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Tells a field set to null from a missing one, which is left to `#[serde(default)]`
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Deserialize)]
+struct InputParams {
+    input: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PageParams {
+    offset: usize,
+    count: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtendLimitParams {
+    additional: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct SelectParams {
+    id: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct Status {
+    total: usize,
+    more: bool,
+    truncated: bool,
+    live: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Item<'a, T> {
+    id: usize,
+    context: &'a T,
+}
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl std::fmt::Display) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// What to do after replying
+enum Next {
+    Continue,
+    Select(usize),
+    Submit,
+    Cancel,
+}
+
+impl<T, R, W, Cushion> UI<Cushion> for RpcUI<T, R, W>
+where
+    T: Serialize + Send,
+    R: AsyncRead + Unpin + Send,
+    W: AsyncWrite + Unpin + Send,
+    Cushion: Send + Sync + 'static,
+{
+    type Context = T;
+
+    async fn run(
+        &self,
+        mut batcher: Batcher<Cushion, Self::Context>,
+    ) -> Result<Option<Selection<Cushion>>> {
+        let mut io = self.io.lock().await;
+        let (lines, writer) = &mut *io;
+
+        let mut buf = Buffer::default();
+        let mut more = true;
+
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let request: Request = match serde_json::from_str(&line) {
+                Ok(request) => request,
+                Err(e) => {
+                    reply(writer, Some(None), Err(RpcError::new(PARSE_ERROR, e))).await?;
+                    continue;
+                }
+            };
+
+            let (result, next) = if request.jsonrpc != "2.0" {
+                (
+                    Err(RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\"")),
+                    Next::Continue,
+                )
+            } else {
+                handle(
+                    &mut batcher,
+                    &mut buf,
+                    &mut more,
+                    request.method,
+                    request.params,
+                )
+                .await
+            };
+
+            reply(writer, request.id, result).await?;
+
+            match next {
+                Next::Continue => {}
+                Next::Select(id) => {
+                    return Ok(Some(Selection::Item(batcher.compute_cushion(id)?)));
+                }
+                Next::Submit => return Ok(Some(Selection::Query(batcher.query().into()))),
+                Next::Cancel => return Ok(None),
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+async fn handle<Cushion, T>(
+    batcher: &mut Batcher<Cushion, T>,
+    buf: &mut Buffer<(T, usize)>,
+    more: &mut bool,
+    method: String,
+    params: Value,
+) -> (Result<Value, RpcError>, Next)
+where
+    Cushion: Send,
+    T: Serialize,
+{
+    let result = match method.as_str() {
+        "input" => match parse::<InputParams>(params) {
+            Ok(p) => {
+                batcher.input(buf, &p.input);
+                *more = true;
+                step(batcher, buf, more).await
+            }
+            Err(e) => Err(e),
+        },
+        "poll" => step(batcher, buf, more).await,
+        "extend_limit" => match parse::<ExtendLimitParams>(params) {
+            Ok(p) => {
+                batcher.extend_limit(buf, p.additional);
+                *more = true;
+                step(batcher, buf, more).await
+            }
+            Err(e) => Err(e),
+        },
+        "page" => parse::<PageParams>(params).and_then(|p| {
+            let items: Vec<_> = buf
+                .as_slice()
+                .iter()
+                .skip(p.offset)
+                .take(p.count)
+                .map(|(context, id)| Item { id: *id, context })
+                .collect();
+
+            serde_json::to_value(items)
+                .map(|items| json!({ "items": items }))
+                .map_err(|e| RpcError::new(LAUNCHER_ERROR, e))
+        }),
+        "select" => {
+            return match parse::<SelectParams>(params) {
+                Ok(p) if !buf.as_slice().iter().any(|(_, id)| *id == p.id) => (
+                    Err(RpcError::new(
+                        INVALID_PARAMS,
+                        format!("item {} is not in the results", p.id),
+                    )),
+                    Next::Continue,
+                ),
+                Ok(p) if batcher.keep_open() => match batcher.act_and_reset(buf, p.id) {
+                    Ok(Flow::Continue) => {
+                        *more = true;
+                        (Ok(json!({ "closed": false })), Next::Continue)
+                    }
+                    Ok(Flow::Close) => (Ok(json!({ "closed": true })), Next::Cancel),
+                    Err(e) => (Err(RpcError::new(LAUNCHER_ERROR, e)), Next::Continue),
+                },
+                Ok(p) => (Ok(Value::Null), Next::Select(p.id)),
+                Err(e) => (Err(e), Next::Continue),
+            };
+        }
+        "submit" if batcher.keep_open() => {
+            return match batcher.act_query_and_reset(buf) {
+                Ok(Flow::Continue) => {
+                    *more = true;
+                    (Ok(json!({ "closed": false })), Next::Continue)
+                }
+                Ok(Flow::Close) => (Ok(json!({ "closed": true })), Next::Cancel),
+                Err(e) => (Err(RpcError::new(LAUNCHER_ERROR, e)), Next::Continue),
+            };
+        }
+        "submit" => return (Ok(Value::Null), Next::Submit),
+        "cancel" => return (Ok(Value::Null), Next::Cancel),
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("unknown method `{method}`"),
+        )),
+    };
+
+    (result, Next::Continue)
+}
+
+/// Processes a batch, or the changes of the live sources, and returns the status
+async fn step<Cushion, T>(
+    batcher: &mut Batcher<Cushion, T>,
+    buf: &mut Buffer<(T, usize)>,
+    more: &mut bool,
+) -> Result<Value, RpcError>
+where
+    Cushion: Send,
+{
+    if *more || batcher.is_live() {
+        let from = batcher.prepare().await;
+        *more = batcher
+            .merge(buf, from)
+            .map_err(|e| RpcError::new(LAUNCHER_ERROR, e))?;
+    }
+
+    serde_json::to_value(Status {
+        total: buf.len(),
+        more: *more,
+        truncated: batcher.is_truncated(),
+        live: batcher.is_live(),
+    })
+    .map_err(|e| RpcError::new(LAUNCHER_ERROR, e))
+}
+
+fn parse<P: DeserializeOwned>(params: Value) -> Result<P, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e))
+}
+
+async fn reply<W>(
+    writer: &mut W,
+    id: Option<Option<Value>>,
+    result: Result<Value, RpcError>,
+) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    // notificationには返さない
+    let Some(id) = id else {
+        return Ok(());
+    };
+
+    let message = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": e.code, "message": e.message },
+        }),
+    };
+
+    let mut line = serde_json::to_vec(&message)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    writer.flush().await?;
+
+    Ok(())
+}
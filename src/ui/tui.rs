@@ -0,0 +1,445 @@
+//! A terminal UI, to see a launcher working without choosing a UI crate first.
+//!
+//! The results are listed above a status line and the query line, like fzf.
+//!
+//! | key                         | operation                                       |
+//! | --------------------------- | ----------------------------------------------- |
+//! | `Enter`                     | select the shown item, or the non-empty query if none |
+//! | `Esc`, `C-c`, `C-g`         | close                                           |
+//! | `Up`, `C-p` / `Down`, `C-n` | move the cursor                                 |
+//! | `PageUp` / `PageDown`       | move the cursor by a page                       |
+//! | others                      | edit the query, see [`Edit`]                    |
+//!
+//! ```no_run
+//! # async fn f() -> ltrait::color_eyre::Result<()> {
+//! use ltrait::{Launcher, ui::tui::Tui};
+//!
+//! Launcher::default()
+//!     .add_source(ltrait::source::from_iter(1..=5000), std::convert::identity)
+//!     .set_ui(Tui::new(), |x: &i32| x.to_string())
+//!     .run()
+//!     .await
+//! # }
+//! ```
+//!
+//! For tests, [`Tui::with_backend`] runs on an in-memory backend such as ratatui's `TestBackend`.
+
+use color_eyre::eyre::{OptionExt, Result};
+use std::fmt::Display;
+use std::io::Stdout;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ratatui::Terminal;
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::Stylize as _;
+use ratatui::text::Line;
+use ratatui::widgets::Paragraph;
+use tokio::sync::mpsc::{UnboundedReceiver, error::TryRecvError, unbounded_channel};
+
+use crate::action::Flow;
+use crate::launcher::batcher::Batcher;
+use crate::ui::input::{Edit, LineEditor};
+use crate::ui::{Buffer, Selection, UI, Viewport};
+
+/// How often the live sources are polled after all the batches are done
+const LIVE_INTERVAL: Duration = Duration::from_millis(100);
+/// How often the event reader checks if the UI has finished
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A terminal UI. `T` is the `UIContext`, shown with [`Display`].
+pub struct Tui<T, B = CrosstermBackend<Stdout>>
+where
+    B: Backend,
+{
+    prompt: String,
+    inline: Option<u16>,
+    /// None to set up the terminal in run
+    backend: Option<Target<B>>,
+
+    _marker: PhantomData<fn(&T)>,
+}
+
+type Events = UnboundedReceiver<Event>;
+
+/// The terminal and the events given by [`Tui::with_backend`]
+struct Target<B: Backend> {
+    terminal: Arc<Mutex<Terminal<B>>>,
+    events: Mutex<Option<Events>>,
+}
+
+impl<T> Tui<T> {
+    /// Runs on the whole terminal
+    pub fn new() -> Self {
+        Self {
+            prompt: "> ".into(),
+            inline: None,
+            backend: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for Tui<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, B> Tui<T, B>
+where
+    B: Backend,
+{
+    /// Runs on `backend` with the events sent to `events`, instead of the terminal.
+    /// The returned terminal is the one drawn on, e.g. to check the buffer of a `TestBackend`.
+    pub fn with_backend(backend: B, events: Events) -> Result<(Self, Arc<Mutex<Terminal<B>>>)> {
+        let terminal = Arc::new(Mutex::new(Terminal::new(backend)?));
+
+        Ok((
+            Self {
+                prompt: "> ".into(),
+                inline: None,
+                backend: Some(Target {
+                    terminal: terminal.clone(),
+                    events: Mutex::new(Some(events)),
+                }),
+                _marker: PhantomData,
+            },
+            terminal,
+        ))
+    }
+
+    /// The text before the query. The default is `"> "`.
+    pub fn prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    /// Draws in `height` lines below the cursor instead of the whole terminal
+    pub fn inline(mut self, height: u16) -> Self {
+        self.inline = Some(height);
+        self
+    }
+}
+
+impl<T, B, Cushion> UI<Cushion> for Tui<T, B>
+where
+    T: Display + Send,
+    B: Backend + Send + 'static,
+    Cushion: Send + Sync + 'static,
+{
+    type Context = T;
+
+    async fn run(
+        &self,
+        batcher: Batcher<Cushion, Self::Context>,
+    ) -> Result<Option<Selection<Cushion>>> {
+        if let Some(Target { terminal, events }) = &self.backend {
+            let mut events = events
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .take()
+                .ok_or_eyre("a Tui with a backend can only be run once")?;
+            return self.run_on(batcher, terminal, &mut events).await;
+        }
+
+        let terminal = match self.inline {
+            Some(height) => ratatui::try_init_with_options(ratatui::TerminalOptions {
+                viewport: ratatui::Viewport::Inline(height),
+            })?,
+            None => ratatui::try_init()?,
+        };
+        let terminal = Mutex::new(terminal);
+
+        // crosstermのreadはblockするので別スレッドで読む
+        let (tx, mut events) = unbounded_channel();
+        std::thread::spawn(move || {
+            while !tx.is_closed() {
+                match event::poll(POLL_INTERVAL) {
+                    Ok(false) => {}
+                    Ok(true) => match event::read() {
+                        Ok(event) => {
+                            let _ = tx.send(event);
+                        }
+                        Err(_) => break,
+                    },
+                    Err(_) => break,
+                }
+            }
+        });
+
+        let result = self.run_on(batcher, &terminal, &mut events).await;
+
+        if self.inline.is_some() {
+            let _ = terminal.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        }
+        ratatui::try_restore()?;
+
+        result
+    }
+}
+
+enum Exit {
+    Item(usize),
+    Query,
+    Cancel,
+}
+
+struct State<T> {
+    line: LineEditor,
+    viewport: Viewport,
+    buf: Buffer<(T, usize)>,
+    more: bool,
+}
+
+impl<T, B> Tui<T, B>
+where
+    T: Display + Send,
+    B: Backend,
+{
+    async fn run_on<Cushion, B2>(
+        &self,
+        mut batcher: Batcher<Cushion, T>,
+        terminal: &Mutex<Terminal<B2>>,
+        events: &mut Events,
+    ) -> Result<Option<Selection<Cushion>>>
+    where
+        Cushion: Send,
+        B2: Backend,
+    {
+        let mut state = State {
+            line: LineEditor::default(),
+            viewport: Viewport::default(),
+            buf: Buffer::default(),
+            more: true,
+        };
+
+        loop {
+            terminal
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .draw(|frame| self.draw(frame, &mut state, &batcher))?;
+
+            // batchの合間にeventを処理する
+            let event = if state.more {
+                match events.try_recv() {
+                    Ok(event) => Some(event),
+                    Err(TryRecvError::Empty) => None,
+                    Err(TryRecvError::Disconnected) => return Ok(None),
+                }
+            } else if batcher.is_live() {
+                match tokio::time::timeout(LIVE_INTERVAL, events.recv()).await {
+                    Ok(Some(event)) => Some(event),
+                    Ok(None) => return Ok(None),
+                    Err(_) => None,
+                }
+            } else {
+                match events.recv().await {
+                    Some(event) => Some(event),
+                    None => return Ok(None),
+                }
+            };
+
+            let exit = match event {
+                Some(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                    state.key(key, &mut batcher).await?
+                }
+                Some(_) => None,
+                None => {
+                    state.step(&mut batcher).await?;
+                    None
+                }
+            };
+
+            match exit {
+                None => {}
+                Some(Exit::Item(id)) => {
+                    return Ok(Some(Selection::Item(batcher.compute_cushion(id)?)));
+                }
+                Some(Exit::Query) => {
+                    return Ok(Some(Selection::Query(batcher.query().into())));
+                }
+                Some(Exit::Cancel) => return Ok(None),
+            }
+        }
+    }
+
+    fn draw<Cushion>(
+        &self,
+        frame: &mut ratatui::Frame,
+        state: &mut State<T>,
+        batcher: &Batcher<Cushion, T>,
+    ) where
+        Cushion: Send,
+    {
+        let [list, status, input] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let rows = state.buf.as_slice();
+        state.viewport.set_height(rows, list.height.into());
+
+        let offset = state.viewport.offset();
+        let lines: Vec<_> = state
+            .viewport
+            .visible(rows)
+            .iter()
+            .enumerate()
+            .map(|(i, (context, _))| {
+                if offset + i == state.viewport.cursor() {
+                    Line::from(format!("> {context}")).reversed()
+                } else {
+                    Line::from(format!("  {context}"))
+                }
+            })
+            .collect();
+        // 下から上に並べる
+        let padding = (list.height as usize).saturating_sub(lines.len());
+        let lines: Vec<_> = std::iter::repeat_n(Line::default(), padding)
+            .chain(lines.into_iter().rev())
+            .collect();
+        frame.render_widget(Paragraph::new(lines), list);
+
+        let progress = if state.more {
+            " ..."
+        } else if batcher.is_truncated() {
+            " +"
+        } else {
+            ""
+        };
+        frame.render_widget(
+            Line::from(format!("  {}{progress}", rows.len())).dim(),
+            status,
+        );
+
+        let prompt = Line::from(self.prompt.as_str());
+        let x = prompt.width() + Line::from(state.line.before_cursor()).width();
+        frame.render_widget(
+            Line::from(format!("{}{}", self.prompt, state.line.text())),
+            input,
+        );
+        frame.set_cursor_position((input.x + x as u16, input.y));
+    }
+}
+
+impl<T> State<T> {
+    /// Processes a batch
+    async fn step<Cushion>(&mut self, batcher: &mut Batcher<Cushion, T>) -> Result<()>
+    where
+        Cushion: Send,
+    {
+        let from = batcher.prepare().await;
+        self.more = batcher.merge(&mut self.buf, from)?;
+        self.viewport.sync(self.buf.as_slice());
+        Ok(())
+    }
+
+    async fn key<Cushion>(
+        &mut self,
+        key: KeyEvent,
+        batcher: &mut Batcher<Cushion, T>,
+    ) -> Result<Option<Exit>>
+    where
+        Cushion: Send,
+    {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let rows = self.buf.as_slice();
+
+        match key.code {
+            KeyCode::Enter => return self.select(batcher).await,
+            KeyCode::Esc => return Ok(Some(Exit::Cancel)),
+            KeyCode::Char('c' | 'g') if ctrl => return Ok(Some(Exit::Cancel)),
+            // 下に行くほど上位なので上下は逆
+            KeyCode::Up => self.viewport.down(rows),
+            KeyCode::Char('p') if ctrl => self.viewport.down(rows),
+            KeyCode::Down => self.viewport.up(rows),
+            KeyCode::Char('n') if ctrl => self.viewport.up(rows),
+            KeyCode::PageUp => self.viewport.page_down(rows),
+            KeyCode::PageDown => self.viewport.page_up(rows),
+            _ => {
+                if let Some(edit) = edit(key) {
+                    self.line.apply(edit);
+                    if self.line.sync(batcher, &mut self.buf) {
+                        self.viewport.reset();
+                        self.more = true;
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn select<Cushion>(&mut self, batcher: &mut Batcher<Cushion, T>) -> Result<Option<Exit>>
+    where
+        Cushion: Send,
+    {
+        // keyは描画の直後に処理するので、bufは表示中の行と同じ
+        // 残りのbatchを待つとmergeで並びが変わるので、表示中の行からidで選ぶ
+        let selected = self
+            .viewport
+            .selected(self.buf.as_slice())
+            .map(|(_, id)| *id);
+        // 空の入力をfallbackに渡しても意味がないので無視する
+        if selected.is_none() && batcher.query().is_empty() {
+            return Ok(None);
+        }
+
+        if !batcher.keep_open() {
+            return Ok(Some(selected.map_or(Exit::Query, Exit::Item)));
+        }
+
+        let flow = match selected {
+            Some(id) => batcher.act_and_reset(&mut self.buf, id)?,
+            None => batcher.act_query_and_reset(&mut self.buf)?,
+        };
+        match flow {
+            Flow::Continue => {
+                self.line = LineEditor::default();
+                self.viewport.reset();
+                self.more = true;
+                Ok(None)
+            }
+            Flow::Close => Ok(Some(Exit::Cancel)),
+        }
+    }
+}
+
+/// The emacs keys of [`Edit`]
+fn edit(key: KeyEvent) -> Option<Edit> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+    Some(match key.code {
+        KeyCode::Char('b') if ctrl => Edit::Left,
+        KeyCode::Char('f') if ctrl => Edit::Right,
+        KeyCode::Char('a') if ctrl => Edit::Home,
+        KeyCode::Char('e') if ctrl => Edit::End,
+        KeyCode::Char('h') if ctrl => Edit::Backspace,
+        KeyCode::Char('d') if ctrl => Edit::Delete,
+        KeyCode::Char('w') if ctrl => Edit::DeleteWordBackward,
+        KeyCode::Char('k') if ctrl => Edit::KillToEnd,
+        KeyCode::Char('u') if ctrl => Edit::KillToStart,
+        KeyCode::Char('y') if ctrl => Edit::Yank,
+        KeyCode::Char('t') if ctrl => Edit::Transpose,
+        KeyCode::Char('b') if alt => Edit::WordLeft,
+        KeyCode::Char('f') if alt => Edit::WordRight,
+        KeyCode::Char('d') if alt => Edit::DeleteWordForward,
+        KeyCode::Backspace if alt => Edit::DeleteWordBackward,
+        KeyCode::Char(_) if ctrl || alt => return None,
+        KeyCode::Char(c) => Edit::Insert(c),
+        KeyCode::Left => Edit::Left,
+        KeyCode::Right => Edit::Right,
+        KeyCode::Home => Edit::Home,
+        KeyCode::End => Edit::End,
+        KeyCode::Backspace => Edit::Backspace,
+        KeyCode::Delete => Edit::Delete,
+        _ => return None,
+    })
+}
@@ -0,0 +1,195 @@
+//! A cursor and a scrolling window over the rows, so that each UI doesn't have to implement them.
+//!
+//! The methods take the rows as a slice of `(context, id)`, e.g. [`Buffer::as_slice`](super::Buffer::as_slice).
+//!
+//! ```
+//! use ltrait::ui::{Buffer, Viewport};
+//!
+//! let mut buf: Buffer<(&str, usize)> = Buffer::default();
+//! buf.push(("a", 0));
+//! buf.push(("b", 1));
+//! buf.push(("c", 2));
+//!
+//! let mut viewport = Viewport::new(2);
+//! viewport.down(buf.as_slice());
+//! viewport.down(buf.as_slice());
+//! assert_eq!(viewport.selected(buf.as_slice()), Some(&("c", 2)));
+//! assert_eq!(viewport.visible(buf.as_slice()), [("b", 1), ("c", 2)]);
+//! ```
+
+/// The cursor and the visible window of the rows
+#[derive(Debug, Clone, Default)]
+pub struct Viewport {
+    cursor: usize,
+    /// The index of the first visible row
+    offset: usize,
+    height: usize,
+    /// The id of the row under the cursor, to follow it in [`Viewport::sync`]
+    selected: Option<usize>,
+}
+
+impl Viewport {
+    /// `height` is the number of the visible rows
+    pub fn new(height: usize) -> Self {
+        Self {
+            height,
+            ..Default::default()
+        }
+    }
+
+    /// Call it when the UI is resized
+    pub fn set_height<T>(&mut self, rows: &[(T, usize)], height: usize) {
+        self.height = height;
+        self.scroll();
+        self.select(rows);
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The index of the row under the cursor
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// The index of the first visible row
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Moves the cursor to the first row, e.g. when the input changes
+    pub fn reset(&mut self) {
+        *self = Self::new(self.height);
+    }
+
+    /// Moves the cursor to `index`. It stops at the last row.
+    pub fn move_to<T>(&mut self, rows: &[(T, usize)], index: usize) {
+        self.cursor = index.min(rows.len().saturating_sub(1));
+        self.scroll();
+        self.select(rows);
+    }
+
+    pub fn up<T>(&mut self, rows: &[(T, usize)]) {
+        self.move_to(rows, self.cursor.saturating_sub(1));
+    }
+
+    pub fn down<T>(&mut self, rows: &[(T, usize)]) {
+        self.move_to(rows, self.cursor + 1);
+    }
+
+    pub fn page_up<T>(&mut self, rows: &[(T, usize)]) {
+        self.move_to(rows, self.cursor.saturating_sub(self.height.max(1)));
+    }
+
+    pub fn page_down<T>(&mut self, rows: &[(T, usize)]) {
+        self.move_to(rows, self.cursor + self.height.max(1));
+    }
+
+    pub fn home<T>(&mut self, rows: &[(T, usize)]) {
+        self.move_to(rows, 0);
+    }
+
+    pub fn end<T>(&mut self, rows: &[(T, usize)]) {
+        self.move_to(rows, usize::MAX);
+    }
+
+    /// Keeps the cursor on the same item after the rows changed, e.g. after [`Batcher::merge`](crate::launcher::batcher::Batcher::merge)
+    /// inserted rows above it. If the item is gone, the cursor stays at the same index.
+    pub fn sync<T>(&mut self, rows: &[(T, usize)]) {
+        let index = self
+            .selected
+            .and_then(|selected| rows.iter().position(|(_, id)| *id == selected))
+            .unwrap_or(self.cursor);
+        self.move_to(rows, index);
+    }
+
+    /// The row under the cursor
+    pub fn selected<'a, T>(&self, rows: &'a [(T, usize)]) -> Option<&'a (T, usize)> {
+        rows.get(self.cursor)
+    }
+
+    /// The rows in the window
+    pub fn visible<'a, T>(&self, rows: &'a [(T, usize)]) -> &'a [(T, usize)] {
+        let start = self.offset.min(rows.len());
+        let end = (self.offset + self.height).min(rows.len());
+        &rows[start..end]
+    }
+
+    fn select<T>(&mut self, rows: &[(T, usize)]) {
+        self.selected = rows.get(self.cursor).map(|(_, id)| *id);
+    }
+
+    /// Scrolls the window as little as possible to show the cursor
+    fn scroll(&mut self) {
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.height > 0 && self.cursor >= self.offset + self.height {
+            self.offset = self.cursor + 1 - self.height;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(ids: impl IntoIterator<Item = usize>) -> Vec<((), usize)> {
+        ids.into_iter().map(|id| ((), id)).collect()
+    }
+
+    #[test]
+    fn movement() {
+        let rows = rows(0..10);
+        let mut viewport = Viewport::new(3);
+
+        viewport.up(&rows);
+        assert_eq!((viewport.cursor(), viewport.offset()), (0, 0));
+
+        viewport.down(&rows);
+        viewport.down(&rows);
+        viewport.down(&rows);
+        assert_eq!((viewport.cursor(), viewport.offset()), (3, 1));
+
+        viewport.page_down(&rows);
+        assert_eq!((viewport.cursor(), viewport.offset()), (6, 4));
+        assert_eq!(viewport.visible(&rows), &rows[4..7]);
+
+        viewport.end(&rows);
+        assert_eq!((viewport.cursor(), viewport.offset()), (9, 7));
+        viewport.page_down(&rows);
+        assert_eq!(viewport.cursor(), 9);
+
+        viewport.page_up(&rows);
+        assert_eq!((viewport.cursor(), viewport.offset()), (6, 6));
+
+        viewport.home(&rows);
+        assert_eq!((viewport.cursor(), viewport.offset()), (0, 0));
+
+        assert_eq!(viewport.visible(&rows[..1]), &rows[..1]);
+        assert_eq!(Viewport::new(3).selected::<()>(&[]), None);
+    }
+
+    #[test]
+    fn sync() {
+        let mut viewport = Viewport::new(2);
+        let before = rows([10, 11, 12]);
+        viewport.down(&before);
+        assert_eq!(viewport.selected(&before), Some(&((), 11)));
+
+        // 上に2行挿入された
+        let after = rows([20, 10, 21, 11, 12]);
+        viewport.sync(&after);
+        assert_eq!(viewport.selected(&after), Some(&((), 11)));
+        assert_eq!((viewport.cursor(), viewport.offset()), (3, 2));
+
+        // 消えたら同じ位置
+        let after = rows([20, 10, 21, 12]);
+        viewport.sync(&after);
+        assert_eq!(viewport.selected(&after), Some(&((), 12)));
+
+        viewport.sync::<()>(&[]);
+        assert_eq!(viewport.selected::<()>(&[]), None);
+        assert_eq!(viewport.cursor(), 0);
+    }
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "config")]
+
+use dummyui::DummyUI;
+use ltrait::config::{Config, Registry};
+use ltrait::filter::ClosureFilter;
+use ltrait::source::from_iter;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+mod dummyui;
+
+fn registry() -> Registry<i32> {
+    Registry::default()
+        .register_source("numbers", |options| {
+            let count = options
+                .get("count")
+                .and_then(|c| c.as_integer())
+                .unwrap_or(10) as i32;
+            Ok(from_iter(0..count))
+        })
+        .register_filter("even", |_| {
+            Ok(Box::new(ClosureFilter::new(|&x: &i32, _| x % 2 == 0)))
+        })
+        .register_filter("multiple", |options| {
+            let of = options
+                .get("weight")
+                .and_then(|c| c.as_integer())
+                .unwrap_or(1) as i32;
+            Ok(Box::new(ClosureFilter::new(move |&x: &i32, _| x % of == 0)))
+        })
+}
+
+#[tokio::test]
+async fn test_config() -> Result<(), Box<dyn std::error::Error>> {
+    let config: Config = r#"
+batch_size = 7
+
+[[source]]
+name = "numbers"
+count = 100
+
+[[filter]]
+name = "even"
+"#
+    .parse()?;
+
+    let count = Arc::new(Mutex::new(0));
+    let count_c = count.clone();
+    let launcher = config.build(&registry())?.set_ui(
+        DummyUI::new(|_: &i32| {
+            *(*count).lock().unwrap() += 1;
+        }),
+        |&c: &i32| c,
+    );
+
+    launcher.run().await?;
+
+    assert_eq!(*(*count_c).lock().unwrap(), 50);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_options() -> Result<(), Box<dyn std::error::Error>> {
+    // `weight`はscorer以外ではただのoption
+    let config: Config = r#"
+[[source]]
+name = "numbers"
+count = 30
+
+[[filter]]
+name = "multiple"
+weight = 3
+"#
+    .parse()?;
+
+    let count = Arc::new(Mutex::new(0));
+    let count_c = count.clone();
+    let launcher = config.build(&registry())?.set_ui(
+        DummyUI::new(move |_: &i32| {
+            *(*count).lock().unwrap() += 1;
+        }),
+        |&c: &i32| c,
+    );
+
+    launcher.run().await?;
+
+    assert_eq!(*(*count_c).lock().unwrap(), 10);
+
+    Ok(())
+}
+
+#[test]
+fn test_config_error() -> Result<(), Box<dyn std::error::Error>> {
+    let config: Config = r#"
+[[source]]
+name = "numbers"
+
+[[filter]]
+name = "odd"
+"#
+    .parse()?;
+
+    let err = config.build(&registry()).err().unwrap();
+    assert_eq!(err.to_string(), "unknown filter `odd` at line 5, column 1");
+
+    let err = "batch_size = \"a\"".parse::<Config>().unwrap_err();
+    assert!(err.to_string().contains("line 1, column 14"), "{err}");
+
+    Ok(())
+}
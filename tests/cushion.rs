@@ -0,0 +1,55 @@
+use ltrait::Launcher;
+use ltrait::UI;
+use ltrait::action::ClosureAction;
+use ltrait::color_eyre::Result;
+use ltrait::launcher::batcher::Batcher;
+use ltrait::source::from_iter;
+use ltrait::ui::{Buffer, Selection};
+
+use std::sync::{Arc, Mutex};
+
+/// Runs the actions on every item without closing, then closes without a selection
+struct PreviewUI;
+
+impl UI<i32> for PreviewUI {
+    type Context = ();
+
+    async fn run(&self, mut batcher: Batcher<i32, ()>) -> Result<Option<Selection<i32>>> {
+        let mut more = true;
+        let mut buf: Buffer<((), usize)> = Buffer::default();
+
+        while more {
+            let from = batcher.prepare().await;
+            more = batcher.merge(&mut buf, from)?;
+        }
+
+        for i in 0..buf.len() {
+            assert_eq!(batcher.cushion_at(&buf, i), Some(&(i as i32)));
+            batcher.act(buf.as_slice()[i].1)?;
+        }
+        assert_eq!(batcher.cushion_at(&buf, buf.len()), None);
+        assert!(batcher.act(usize::MAX).is_err());
+
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn test_act_without_closing() -> Result<()> {
+    let acted = Arc::new(Mutex::new(vec![]));
+    let acted_c = acted.clone();
+
+    Launcher::default()
+        .add_source(from_iter(0..3), std::convert::identity)
+        .add_raw_action(ClosureAction::new(move |&x: &i32| {
+            acted_c.lock().unwrap().push(x);
+            Ok(())
+        }))
+        .set_ui(PreviewUI, |_| ())
+        .run()
+        .await?;
+
+    assert_eq!(*acted.lock().unwrap(), [0, 1, 2]);
+
+    Ok(())
+}
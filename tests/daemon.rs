@@ -0,0 +1,190 @@
+#![cfg(all(unix, feature = "daemon"))]
+
+use ltrait::Launcher;
+use ltrait::action::ClosureAction;
+use ltrait::daemon::{Client, Refresh, Remote, Server};
+use ltrait::filter::ClosureFilter;
+use ltrait::source::from_iter;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_daemon() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join(format!("ltrait-test-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let builds = Arc::new(AtomicUsize::new(0));
+    let selected = Arc::new(AtomicUsize::new(0));
+
+    let server = {
+        let builds = builds.clone();
+        let selected = selected.clone();
+
+        Server::new(move || {
+            builds.fetch_add(1, Ordering::SeqCst);
+            let selected = selected.clone();
+
+            Ok(Launcher::default()
+                .add_source(from_iter(0..100), std::convert::identity)
+                .add_raw_filter(ClosureFilter::new(|x: &i32, input: &str| {
+                    x.to_string().contains(input)
+                }))
+                .add_raw_action(ClosureAction::new(move |&x: &i32| {
+                    selected.store(x as usize, Ordering::SeqCst);
+                    Ok(())
+                }))
+                .set_ui(Remote::default(), |&x: &i32| x))
+        })
+        .refresh(Refresh::Interval(Duration::from_millis(50)))
+    };
+    let server = tokio::spawn(server.serve(path.clone()));
+
+    let mut client = loop {
+        match Client::<i32>::connect(&path).await {
+            Ok(client) => break client,
+            Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+        }
+    };
+
+    let page = client.input("1").await?;
+    assert_eq!(page.items.len(), 19);
+    assert!(!page.truncated);
+
+    // refreshと重なったら入力からやり直す
+    loop {
+        let page = client.input("42").await?;
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].context, 42);
+
+        if client.select(page.items[0].id).await.is_ok() {
+            break;
+        }
+    }
+    assert_eq!(selected.load(Ordering::SeqCst), 42);
+
+    assert!(client.select(usize::MAX).await.is_err());
+    // エラーの後も使える
+    assert_eq!(client.input("").await?.items.len(), 100);
+
+    let page = client.input("99").await?;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(builds.load(Ordering::SeqCst) >= 2);
+
+    // 古いgenerationのidは別のitemを指すかもしれない
+    let err = client.select(page.items[0].id).await.unwrap_err();
+    assert!(err.to_string().contains("refreshed"));
+    assert!(client.input("99").await?.generation > page.generation);
+
+    server.abort();
+    std::fs::remove_file(&path)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_daemon_try_source() -> Result<(), Box<dyn std::error::Error>> {
+    use ltrait::color_eyre::eyre::eyre;
+
+    let path = std::env::temp_dir().join(format!("ltrait-test-try-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let server = Server::new(|| {
+        Ok(Launcher::default()
+            .add_source(from_iter(0..10), std::convert::identity)
+            .add_try_source(
+                from_iter(vec![Ok(10), Err(eyre!("failed to read")), Ok(11)]),
+                std::convert::identity,
+            )
+            .set_ui(Remote::default(), |&x: &i32| x))
+    });
+    let server = tokio::spawn(server.serve(path.clone()));
+
+    let mut client = loop {
+        match Client::<i32>::connect(&path).await {
+            Ok(client) => break client,
+            Err(_) => {
+                // sourceのerrorでserverが止まってはいけない
+                assert!(!server.is_finished());
+                tokio::time::sleep(Duration::from_millis(10)).await
+            }
+        }
+    };
+
+    // 失敗したsourceがあっても他のitemは返る
+    assert_eq!(client.input("").await?.items.len(), 12);
+
+    server.abort();
+    std::fs::remove_file(&path)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_daemon_clients() -> Result<(), Box<dyn std::error::Error>> {
+    use ltrait::generator::ClosureGenerator;
+
+    let path =
+        std::env::temp_dir().join(format!("ltrait-test-clients-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let selected = Arc::new(std::sync::Mutex::new(vec![]));
+    let server = {
+        let selected = selected.clone();
+
+        Server::new(move || {
+            let selected = selected.clone();
+
+            Ok(Launcher::default()
+                .add_source(from_iter(0..100), std::convert::identity)
+                .add_raw_generator(ClosureGenerator::new(|input: &str| {
+                    input
+                        .parse()
+                        .map(|x: i32| vec![x * 1000])
+                        .unwrap_or_default()
+                }))
+                .add_raw_filter(ClosureFilter::new(|x: &i32, input: &str| {
+                    x.to_string().contains(input)
+                }))
+                .add_raw_action(ClosureAction::new(move |&x: &i32| {
+                    selected.lock().unwrap().push(x);
+                    Ok(())
+                }))
+                .set_ui(Remote::default(), |&x: &i32| x))
+        })
+    };
+    let server = tokio::spawn(server.serve(path.clone()));
+
+    let connect = async || loop {
+        match Client::<i32>::connect(&path).await {
+            Ok(client) => break client,
+            Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+        }
+    };
+    let mut a = connect().await;
+    let mut b = connect().await;
+
+    let id = |page: &ltrait::daemon::Page<i32>, x: i32| {
+        page.items.iter().find(|item| item.context == x).unwrap().id
+    };
+
+    // 交互に入力しても、それぞれの接続のpageのままで選べる
+    let page_a = a.input("7").await?;
+    let page_b = b.input("42").await?;
+    assert_eq!(page_a.items.len(), 20);
+    assert_eq!(page_b.items.len(), 2);
+
+    a.select(id(&page_a, 7000)).await?;
+    b.select(id(&page_b, 42000)).await?;
+    a.select(id(&page_a, 17)).await?;
+    assert_eq!(*selected.lock().unwrap(), [7000, 42000, 17]);
+
+    // 他の接続のpageのidは選べない
+    let err = b.select(id(&page_a, 17)).await.unwrap_err();
+    assert!(err.to_string().contains("last page"));
+
+    server.abort();
+    std::fs::remove_file(&path)?;
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use dummyui::DummyUI;
+use ltrait::launcher::dedup::DedupPolicy;
+use ltrait::sorter::ClosureSorter;
+use ltrait::{Launcher, source::from_iter};
+use std::sync::Arc;
+use std::sync::Mutex;
+
+mod dummyui;
+
+#[tokio::test]
+async fn test_dedup() -> Result<(), Box<dyn std::error::Error>> {
+    let shown = Arc::new(Mutex::new(vec![]));
+    let shown_c = shown.clone();
+    let launcher = Launcher::default()
+        .add_source(from_iter(0..50), |x| (x, 'a'))
+        .add_source(from_iter(25..75), |x| (x, 'b'))
+        .batch_size(10)
+        .dedup(
+            |&(x, _): &(i32, char)| x,
+            DedupPolicy::SourcePriority(vec![1]),
+        )
+        .add_raw_sorter(ClosureSorter::new(|lhs: &(i32, char), rhs, _| {
+            lhs.0.cmp(&rhs.0)
+        }))
+        .set_ui(
+            DummyUI::new(|&c: &(i32, char)| {
+                (*shown).lock().unwrap().push(c);
+            }),
+            |&c| c,
+        );
+
+    launcher.run().await?;
+
+    let expected: Vec<_> = (0..25)
+        .map(|x| (x, 'a'))
+        .chain((25..75).map(|x| (x, 'b')))
+        .collect();
+    assert_eq!(*(*shown_c).lock().unwrap(), expected);
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+#![cfg(feature = "derive")]
+
+mod dummyui;
+
+use dummyui::DummyUI;
+
+use ltrait::color_eyre::Result;
+use ltrait::filter::ClosureFilter;
+use ltrait::source::from_iter;
+use ltrait::{Cushion, Launcher};
+
+use std::sync::{Arc, Mutex};
+
+#[derive(Cushion, Debug, PartialEq)]
+enum Item {
+    #[cushion(display = "{0}")]
+    Num(u32),
+    #[cushion(display = "{0}:{1}", haystack = "{1}")]
+    Pair(u32, String),
+    #[cushion(display = "{name}", haystack = "{name} {path}")]
+    App { name: String, path: String },
+    #[cushion(display = "{text}")]
+    Text { text: String },
+    #[cushion(display = "quit")]
+    Quit,
+    // raw identifier
+    #[cushion(display = "{0}")]
+    Type(String),
+    #[cushion(display = "{0}")]
+    HTTPGet(f64),
+}
+
+#[test]
+fn test_derive() {
+    assert_eq!(Item::num(1), Item::Num(1));
+    assert_eq!(Item::from(1), Item::Num(1));
+    assert_eq!(Item::pair((1, "a".into())), Item::Pair(1, "a".into()));
+    assert_eq!(Item::text("a".into()), Item::Text { text: "a".into() });
+    assert_eq!(Item::r#type("a".into()), Item::Type("a".into()));
+    // Stringは2つあるのでFromはない
+    assert_eq!(Item::http_get(1.0), Item::HTTPGet(1.0));
+    assert_eq!(Item::from(1.0), Item::HTTPGet(1.0));
+
+    let app = Item::app(("Firefox".into(), "/usr/bin/firefox".into()));
+    assert_eq!(app.display_string(), "Firefox");
+    assert_eq!(app.haystack(), "Firefox /usr/bin/firefox");
+
+    assert_eq!(Item::pair((1, "a".into())).display_string(), "1:a");
+    assert_eq!(Item::pair((1, "a".into())).haystack(), "a");
+    assert_eq!(Item::Quit.haystack(), "quit");
+}
+
+#[tokio::test]
+async fn test_derive_launcher() -> Result<()> {
+    let shown = Arc::new(Mutex::new(vec![]));
+    let shown_c = shown.clone();
+
+    Launcher::default()
+        .add_source(from_iter(1..=20), Item::num)
+        .add_source(
+            from_iter([("Firefox".to_string(), "/usr/bin/firefox".to_string())]),
+            Item::app,
+        )
+        .add_raw_filter(ClosureFilter::new(|c: &Item, _| c.haystack().contains('1')))
+        .set_ui(
+            DummyUI::new(move |s: &String| shown_c.lock().unwrap().push(s.clone())),
+            Item::display_string,
+        )
+        .run()
+        .await?;
+
+    assert_eq!(
+        *shown.lock().unwrap(),
+        [
+            "1", "10", "11", "12", "13", "14", "15", "16", "17", "18", "19"
+        ]
+    );
+
+    Ok(())
+}
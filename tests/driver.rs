@@ -0,0 +1,139 @@
+#![cfg(feature = "driver")]
+
+use ltrait::Launcher;
+use ltrait::UI;
+use ltrait::color_eyre::Result;
+use ltrait::filter::ClosureFilter;
+use ltrait::launcher::batcher::Batcher;
+use ltrait::sorter::ClosureSorter;
+use ltrait::source::from_iter;
+use ltrait::tokio_stream::StreamExt as _;
+use ltrait::ui::Selection;
+use ltrait::ui::driver::{Driver, Rows, Update};
+
+struct DriverUI;
+
+impl UI<i32> for DriverUI {
+    type Context = i32;
+
+    async fn run(&self, batcher: Batcher<i32, i32>) -> Result<Option<Selection<i32>>> {
+        let mut driver = Driver::spawn(batcher);
+        let mut rows = Rows::default();
+
+        let mut resets = 0;
+        let mut changes = 0;
+        while let Some(update) = driver.next().await {
+            match &update {
+                Update::Reset => resets += 1,
+                Update::Changed { .. } => changes += 1,
+                _ => {}
+            }
+            rows.apply(update);
+            if rows.is_done() {
+                break;
+            }
+        }
+        assert_eq!(resets, 1);
+        assert_eq!(changes, 10);
+        assert_eq!(rows.len(), 100);
+        // 大きい順
+        assert!(rows.as_slice().windows(2).all(|w| w[0].0 > w[1].0));
+
+        driver.input("9");
+        while let Some(update) = driver.next().await {
+            rows.apply(update);
+            if rows.is_done() {
+                break;
+            }
+        }
+        assert_eq!(
+            rows.as_slice().iter().map(|(x, _)| *x).collect::<Vec<_>>()[..3],
+            [99, 98, 97]
+        );
+        assert_eq!(rows.len(), 19);
+
+        let id = rows.as_slice()[0].1;
+        let batcher = driver.finish().await?;
+        Ok(Some(Selection::Item(batcher.compute_cushion(id)?)))
+    }
+}
+
+#[tokio::test]
+async fn test_driver() -> Result<()> {
+    let selected = std::sync::Arc::new(std::sync::Mutex::new(None));
+    let selected_c = selected.clone();
+
+    Launcher::default()
+        .add_source(from_iter(0..100), std::convert::identity)
+        .add_raw_filter(ClosureFilter::new(|x: &i32, input: &str| {
+            x.to_string().contains(input)
+        }))
+        .add_raw_sorter(ClosureSorter::new(|lhs: &i32, rhs: &i32, _| rhs.cmp(lhs)))
+        .add_raw_action(ltrait::action::ClosureAction::new(move |&x: &i32| {
+            *selected_c.lock().unwrap() = Some(x);
+            Ok(())
+        }))
+        .batch_size(10)
+        .set_ui(DriverUI, |&x: &i32| x)
+        .run()
+        .await?;
+
+    assert_eq!(*selected.lock().unwrap(), Some(99));
+
+    Ok(())
+}
+
+struct ErrorUI;
+
+impl UI<i32> for ErrorUI {
+    type Context = i32;
+
+    async fn run(&self, batcher: Batcher<i32, i32>) -> Result<Option<Selection<i32>>> {
+        let mut driver = Driver::spawn(batcher);
+        let mut rows = Rows::default();
+
+        while let Some(update) = driver.next().await {
+            rows.apply(update);
+            if rows.is_done() {
+                break;
+            }
+        }
+        // errorがあっても残りのitemは届く
+        assert_eq!(rows.error(), Some("failed to read"));
+        assert_eq!(rows.len(), 12);
+
+        driver.input("1");
+        while let Some(update) = driver.next().await {
+            rows.apply(update);
+            if rows.is_done() {
+                break;
+            }
+        }
+        assert_eq!(rows.error(), None);
+        assert_eq!(rows.len(), 3);
+
+        driver.finish().await?;
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn test_driver_error() -> Result<()> {
+    use ltrait::color_eyre::eyre::eyre;
+
+    Launcher::default()
+        .add_source(from_iter(0..10), std::convert::identity)
+        .add_try_source(
+            from_iter(vec![Ok(10), Err(eyre!("failed to read")), Ok(11)]),
+            std::convert::identity,
+        )
+        .add_raw_filter(ClosureFilter::new(|x: &i32, input: &str| {
+            x.to_string().contains(input)
+        }))
+        .batch_size(4)
+        .set_ui(ErrorUI, |&x: &i32| x)
+        .run()
+        .await?;
+
+    Ok(())
+}
@@ -2,7 +2,7 @@ use ltrait::{
     UI,
     color_eyre::eyre::Result,
     launcher::batcher::Batcher,
-    ui::{Buffer, Position},
+    ui::{Buffer, Position, Selection},
 };
 
 use std::marker::PhantomData;
@@ -37,7 +37,10 @@ where
 {
     type Context = T;
 
-    async fn run(&self, mut batcher: Batcher<Cushion, Self::Context>) -> Result<Option<Cushion>> {
+    async fn run(
+        &self,
+        mut batcher: Batcher<Cushion, Self::Context>,
+    ) -> Result<Option<Selection<Cushion>>> {
         let mut more = true;
         let mut buf: Buffer<(T, usize)> = Buffer::default();
 
@@ -48,7 +51,7 @@ where
 
         let mut pos = Position::default();
         let mut least_one = false;
-        while let Some(&(ref c, _)) = buf.next(&mut pos) {
+        while let Some((c, _)) = buf.next(&mut pos) {
             (self.f)(c);
             if !least_one {
                 least_one = true;
@@ -56,7 +59,7 @@ where
         }
 
         if least_one {
-            Ok(Some(batcher.compute_cushion(0)?))
+            Ok(Some(Selection::Item(batcher.compute_cushion(0)?)))
         } else {
             Ok(None)
         }
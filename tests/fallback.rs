@@ -0,0 +1,78 @@
+use ltrait::Launcher;
+use ltrait::UI;
+use ltrait::action::{ClosureAction, ClosureFlowAction, Flow};
+use ltrait::color_eyre::Result;
+use ltrait::filter::ClosureFilter;
+use ltrait::launcher::batcher::Batcher;
+use ltrait::source::from_iter;
+use ltrait::ui::{Buffer, Selection};
+
+use std::sync::{Arc, Mutex};
+
+/// Types the input, and submits it if nothing matches
+struct QueryUI(&'static str);
+
+impl UI<String> for QueryUI {
+    type Context = ();
+
+    async fn run(&self, mut batcher: Batcher<String, ()>) -> Result<Option<Selection<String>>> {
+        let mut buf: Buffer<((), usize)> = Buffer::default();
+
+        loop {
+            batcher.input(&mut buf, self.0);
+            let mut more = true;
+            while more {
+                let from = batcher.prepare().await;
+                more = batcher.merge(&mut buf, from)?;
+            }
+
+            if let Some(((), id)) = buf.as_slice().first() {
+                return Ok(Some(Selection::Item(batcher.compute_cushion(*id)?)));
+            }
+            if !batcher.keep_open() {
+                return Ok(Some(Selection::Query(batcher.query().into())));
+            }
+            if batcher.act_query_and_reset(&mut buf)? == Flow::Close {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+fn launcher(input: &'static str, log: Arc<Mutex<Vec<String>>>) -> Launcher<String, QueryUI, ()> {
+    let item_log = log.clone();
+
+    Launcher::default()
+        .add_source(from_iter(["firefox", "foot"]), String::from)
+        .add_raw_filter(ClosureFilter::new(|x: &String, input: &str| {
+            x.starts_with(input)
+        }))
+        .add_raw_action(ClosureAction::new(move |x: &String| {
+            item_log.lock().unwrap().push(format!("item {x}"));
+            Ok(())
+        }))
+        .add_raw_fallback_action(ClosureFlowAction::new(move |q: &String| {
+            log.lock().unwrap().push(format!("query {q}"));
+            Ok(Flow::Close)
+        }))
+        .set_ui(QueryUI(input), |_| ())
+}
+
+#[tokio::test]
+async fn test_fallback() -> Result<()> {
+    let log = Arc::new(Mutex::new(vec![]));
+
+    launcher("firefox --private", log.clone()).run().await?;
+    launcher("fire", log.clone()).run().await?;
+    assert_eq!(
+        *log.lock().unwrap(),
+        ["query firefox --private", "item firefox"]
+    );
+
+    // keep_openでもCloseで閉じる
+    log.lock().unwrap().clear();
+    launcher("bar", log.clone()).keep_open(true).run().await?;
+    assert_eq!(*log.lock().unwrap(), ["query bar"]);
+
+    Ok(())
+}
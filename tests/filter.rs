@@ -29,3 +29,45 @@ async fn test_filter() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Counts the calls of `predicate_batch`
+struct BatchFilter(Arc<Mutex<Vec<usize>>>);
+
+impl ltrait::Filter for BatchFilter {
+    type Context = i32;
+
+    fn predicate(&self, _: &Self::Context, _: &str) -> bool {
+        unreachable!("the launcher should call predicate_batch")
+    }
+
+    fn predicate_batch(&self, ctxs: &[&Self::Context], _: &str) -> Vec<bool> {
+        self.0.lock().unwrap().push(ctxs.len());
+        ctxs.iter().map(|&&x| x % 3 == 0).collect()
+    }
+}
+
+#[tokio::test]
+async fn test_filter_batch() -> Result<(), Box<dyn std::error::Error>> {
+    let calls = Arc::new(Mutex::new(vec![]));
+    let count = Arc::new(Mutex::new(0));
+    let count_c = count.clone();
+    let launcher = Launcher::default()
+        .add_source(from_iter(0..COUNT), identity)
+        .add_filter(ClosureFilter::new(|&x: &i32, _| (x % 2) == 0), |&c: &i32| c)
+        .add_filter(BatchFilter(calls.clone()), |&c: &i32| c)
+        .batch_size(1000)
+        .set_ui(
+            DummyUI::new(|_: &i32| {
+                *(*count).lock().unwrap() += 1;
+            }),
+            |&c: &i32| c,
+        );
+
+    launcher.run().await?;
+
+    assert_eq!(*(*count_c).lock().unwrap(), (COUNT + 5) / 6);
+    // 前のfilterで落ちたものは渡されない
+    assert_eq!(*calls.lock().unwrap(), vec![500; 5]);
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "input")]
+
+use ltrait::Launcher;
+use ltrait::UI;
+use ltrait::color_eyre::Result;
+use ltrait::filter::ClosureFilter;
+use ltrait::launcher::batcher::Batcher;
+use ltrait::source::from_iter;
+use ltrait::ui::input::{Edit, LineEditor};
+use ltrait::ui::{Buffer, Selection};
+
+struct EditUI;
+
+impl UI<i32> for EditUI {
+    type Context = i32;
+
+    async fn run(&self, mut batcher: Batcher<i32, i32>) -> Result<Option<Selection<i32>>> {
+        let mut buf: Buffer<(i32, usize)> = Buffer::default();
+        let mut line = LineEditor::default();
+
+        let mut lengths = vec![];
+        for edit in [
+            Edit::Insert('1'),
+            Edit::Insert('2'),
+            Edit::Left,
+            Edit::Backspace,
+            Edit::Home,
+        ] {
+            line.apply(edit);
+            if !line.sync(&mut batcher, &mut buf) {
+                continue;
+            }
+
+            let mut more = true;
+            while more {
+                let from = batcher.prepare().await;
+                more = batcher.merge(&mut buf, from)?;
+            }
+            lengths.push(buf.len());
+        }
+
+        // "1", "12", "2"
+        assert_eq!(lengths, [19, 1, 19]);
+        assert_eq!(batcher.query(), "2");
+
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn test_line_editor() -> Result<()> {
+    Launcher::default()
+        .add_source(from_iter(0..100), std::convert::identity)
+        .add_raw_filter(ClosureFilter::new(|x: &i32, input: &str| {
+            x.to_string().contains(input)
+        }))
+        .set_ui(EditUI, |&x| x)
+        .run()
+        .await
+}
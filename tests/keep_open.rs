@@ -0,0 +1,73 @@
+use ltrait::Launcher;
+use ltrait::UI;
+use ltrait::action::{ClosureFlowAction, Flow};
+use ltrait::color_eyre::Result;
+use ltrait::filter::ClosureFilter;
+use ltrait::launcher::batcher::Batcher;
+use ltrait::source::from_iter;
+use ltrait::ui::{Buffer, Selection};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Types the inputs in order and selects the first item each time
+struct LoopUI(Vec<&'static str>);
+
+impl UI<i32> for LoopUI {
+    type Context = i32;
+
+    async fn run(&self, mut batcher: Batcher<i32, i32>) -> Result<Option<Selection<i32>>> {
+        assert!(batcher.keep_open());
+        let mut buf: Buffer<(i32, usize)> = Buffer::default();
+
+        for input in &self.0 {
+            batcher.input(&mut buf, input);
+            let mut more = true;
+            while more {
+                let from = batcher.prepare().await;
+                more = batcher.merge(&mut buf, from)?;
+            }
+
+            let id = buf.as_slice()[0].1;
+            match batcher.act_and_reset(&mut buf, id)? {
+                Flow::Continue => assert!(buf.is_empty()),
+                Flow::Close => return Ok(None),
+            }
+        }
+
+        panic!("the launcher was not closed");
+    }
+}
+
+#[tokio::test]
+async fn test_keep_open() -> Result<()> {
+    let sourced = Arc::new(AtomicUsize::new(0));
+    let sourced_c = sourced.clone();
+    let acted = Arc::new(Mutex::new(vec![]));
+    let acted_c = acted.clone();
+
+    Launcher::default()
+        .add_source(
+            from_iter((0..10).inspect(move |_| {
+                sourced_c.fetch_add(1, Ordering::Relaxed);
+            })),
+            std::convert::identity,
+        )
+        .add_raw_filter(ClosureFilter::new(|x: &i32, input: &str| {
+            x.to_string().contains(input)
+        }))
+        .add_raw_action(ClosureFlowAction::new(move |&x: &i32| {
+            acted_c.lock().unwrap().push(x);
+            Ok(if x == 9 { Flow::Close } else { Flow::Continue })
+        }))
+        .keep_open(true)
+        .set_ui(LoopUI(vec!["3", "5", "9", "1"]), |&x| x)
+        .run()
+        .await?;
+
+    assert_eq!(*acted.lock().unwrap(), [3, 5, 9]);
+    // sourceは最初の一回だけ
+    assert_eq!(sourced.load(Ordering::Relaxed), 10);
+
+    Ok(())
+}
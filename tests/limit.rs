@@ -0,0 +1,35 @@
+use dummyui::DummyUI;
+use ltrait::sorter::ClosureSorter;
+use ltrait::{Launcher, source::from_iter};
+use std::convert::identity;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+mod dummyui;
+
+const COUNT: i32 = 5000;
+const LIMIT: usize = 10;
+
+#[tokio::test]
+async fn test_limit() -> Result<(), Box<dyn std::error::Error>> {
+    let order = Arc::new(Mutex::new(vec![]));
+    let order_c = order.clone();
+    let launcher = Launcher::default()
+        .add_source(from_iter(0..COUNT), identity)
+        .batch_size(37)
+        .limit(LIMIT)
+        .add_raw_sorter(ClosureSorter::new(|lhs: &i32, rhs, _| rhs.cmp(lhs)))
+        .set_ui(
+            DummyUI::new(|&x: &i32| {
+                (*order).lock().unwrap().push(x);
+            }),
+            |&c: &i32| c,
+        );
+
+    launcher.run().await?;
+
+    let expected: Vec<_> = (0..COUNT).rev().take(LIMIT).collect();
+    assert_eq!(*(*order_c).lock().unwrap(), expected);
+
+    Ok(())
+}
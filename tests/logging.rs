@@ -0,0 +1,37 @@
+#![cfg(feature = "log")]
+
+use ltrait::Level;
+use ltrait::logging::{Format, Logging, Rotation};
+
+#[test]
+fn test_logging() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("ltrait-test-log-{}", std::process::id()));
+
+    let guard = Logging::new(Level::WARN)
+        .dir(&dir)
+        .rotation(Rotation::Never)
+        .max_files(1)
+        .filter("logging=debug")
+        .format(Format::Json)
+        .init()?;
+
+    tracing::debug!(target: "logging", "kept");
+    tracing::debug!(target: "other", "dropped");
+    drop(guard);
+
+    let log = std::fs::read_to_string(dir.join("core.log"))?;
+
+    let lines: Vec<serde_json::Value> = log
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["fields"]["message"], "kept");
+
+    // 二回目は失敗する
+    assert!(Logging::new(Level::WARN).dir(&dir).init().is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+
+    Ok(())
+}
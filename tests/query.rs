@@ -0,0 +1,74 @@
+use ltrait::Launcher;
+use ltrait::UI;
+use ltrait::color_eyre::Result;
+use ltrait::launcher::batcher::Batcher;
+use ltrait::query::{QueryFilter, Schema};
+use ltrait::source::from_iter;
+use ltrait::ui::{Buffer, Selection};
+
+use std::sync::{Arc, Mutex};
+
+/// Types the query and collects the results
+struct QueryUI(&'static str, Arc<Mutex<Vec<i32>>>);
+
+impl UI<i32> for QueryUI {
+    type Context = i32;
+
+    async fn run(&self, mut batcher: Batcher<i32, i32>) -> Result<Option<Selection<i32>>> {
+        let mut buf: Buffer<(i32, usize)> = Buffer::default();
+
+        batcher.input(&mut buf, self.0);
+        let mut more = true;
+        while more {
+            let from = batcher.prepare().await;
+            more = batcher.merge(&mut buf, from)?;
+        }
+
+        *self.1.lock().unwrap() = buf.as_slice().iter().map(|(x, _)| *x).collect();
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn test_query() -> Result<()> {
+    let schema = Arc::new(
+        Schema::new()
+            .text("name", |x: &i32| format!("item{x}"))
+            .number("n", |x: &i32| *x as f64)
+            .tags("tag", |x: &i32| {
+                vec![if x % 2 == 0 { "even" } else { "odd" }.to_string()]
+            }),
+    );
+    let results = Arc::new(Mutex::new(vec![]));
+
+    Launcher::default()
+        .add_source(from_iter(0..100), std::convert::identity)
+        .add_raw_filter(QueryFilter::new(schema.clone()))
+        .set_ui(
+            QueryUI("tag:even n<10 -name:item4 colour:red", results.clone()),
+            |x: &i32| *x,
+        )
+        .run()
+        .await?;
+
+    // 未知のfieldは単語として扱われるので何も残らない
+    assert!(results.lock().unwrap().is_empty());
+    let diagnostics = schema.parse("colour:red").diagnostics().to_vec();
+    assert_eq!(diagnostics[0].to_string(), "unknown field `colour`");
+
+    Launcher::default()
+        .add_source(from_iter(0..100), std::convert::identity)
+        .add_raw_filter(QueryFilter::new(schema))
+        .set_ui(
+            QueryUI("tag:even n<10 -name:item4", results.clone()),
+            |x: &i32| *x,
+        )
+        .run()
+        .await?;
+
+    let mut results = results.lock().unwrap().clone();
+    results.sort();
+    assert_eq!(results, [0, 2, 6, 8]);
+
+    Ok(())
+}
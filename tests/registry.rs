@@ -0,0 +1,71 @@
+#![cfg(feature = "registry")]
+
+use dummyui::DummyUI;
+use ltrait::Launcher;
+use ltrait::filter::ClosureFilter;
+use ltrait::registry::{Registry, toml::Table};
+use ltrait::source::from_iter;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+mod dummyui;
+
+fn plugin(registry: Registry<i32>) -> Registry<i32> {
+    registry
+        .register_source("numbers", |options| {
+            let count = options
+                .get("count")
+                .and_then(|c| c.as_integer())
+                .unwrap_or(10) as i32;
+            Ok(from_iter(0..count))
+        })
+        .register_filter("multiple", |options| {
+            let n = options
+                .get("n")
+                .and_then(|n| n.as_integer())
+                .ok_or_else(|| ltrait::color_eyre::eyre::eyre!("`n` is required"))?
+                as i32;
+            Ok(Box::new(ClosureFilter::new(move |&x: &i32, _| x % n == 0)))
+        })
+}
+
+#[tokio::test]
+async fn test_named() -> Result<(), Box<dyn std::error::Error>> {
+    let registry = Registry::default().plugin(plugin);
+
+    let count = Arc::new(Mutex::new(0));
+    let count_c = count.clone();
+    let launcher = Launcher::default()
+        .add_named_source(&registry, "numbers", &toml_table("count = 100")?)?
+        .add_named_filter(&registry, "multiple", &toml_table("n = 3")?)?
+        .set_ui(
+            DummyUI::new(|_: &i32| {
+                *(*count).lock().unwrap() += 1;
+            }),
+            |&c: &i32| c,
+        );
+
+    launcher.run().await?;
+
+    assert_eq!(*(*count_c).lock().unwrap(), 34);
+
+    Ok(())
+}
+
+#[test]
+fn test_named_error() {
+    let registry = Registry::default().plugin(plugin);
+
+    let err = registry.sorter("length", &Table::new()).err().unwrap();
+    assert_eq!(err.to_string(), "unknown sorter `length`");
+
+    let err = registry.filter("multiple", &Table::new()).err().unwrap();
+    assert_eq!(
+        format!("{err:#}"),
+        "failed to create filter `multiple`: `n` is required"
+    );
+}
+
+fn toml_table(s: &str) -> Result<Table, Box<dyn std::error::Error>> {
+    Ok(s.parse()?)
+}
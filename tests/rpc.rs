@@ -0,0 +1,142 @@
+#![cfg(feature = "rpc")]
+
+use ltrait::Launcher;
+use ltrait::action::ClosureAction;
+use ltrait::filter::ClosureFilter;
+use ltrait::source::from_iter;
+use ltrait::ui::rpc::RpcUI;
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+#[tokio::test]
+async fn test_rpc() -> Result<(), Box<dyn std::error::Error>> {
+    let (front, back) = tokio::io::duplex(1024);
+    let (back_read, back_write) = tokio::io::split(back);
+    let (front_read, mut front_write) = tokio::io::split(front);
+    let mut responses = BufReader::new(front_read).lines();
+
+    let selected = Arc::new(Mutex::new(None));
+    let selected_c = selected.clone();
+
+    let launcher = Launcher::default()
+        .add_source(from_iter(0..100), std::convert::identity)
+        .add_raw_filter(ClosureFilter::new(|x: &i32, input: &str| {
+            x.to_string().contains(input)
+        }))
+        .add_raw_action(ClosureAction::new(move |&x: &i32| {
+            *selected_c.lock().unwrap() = Some(x);
+            Ok(())
+        }))
+        .batch_size(30)
+        .set_ui(RpcUI::new(back_read, back_write), |x: &i32| x.to_string());
+    let launcher = tokio::spawn(launcher.run());
+
+    let mut call = async |request: Value| -> Result<Value, Box<dyn std::error::Error>> {
+        front_write
+            .write_all(format!("{request}\n").as_bytes())
+            .await?;
+        let line = responses.next_line().await?.unwrap();
+        Ok(serde_json::from_str(&line)?)
+    };
+
+    let status =
+        call(json!({"jsonrpc": "2.0", "id": 1, "method": "input", "params": {"input": "4"}}))
+            .await?;
+    assert_eq!(status["result"]["more"], true);
+
+    let mut status = status;
+    while status["result"]["more"] == true {
+        status = call(json!({"jsonrpc": "2.0", "id": 2, "method": "poll"})).await?;
+    }
+    assert_eq!(status["result"]["total"], 19);
+
+    let page = call(
+        json!({"jsonrpc": "2.0", "id": 3, "method": "page", "params": {"offset": 0, "count": 2}}),
+    )
+    .await?;
+    let items = page["result"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["context"], "4");
+
+    let error = call(json!({"jsonrpc": "2.0", "id": 4, "method": "foo"})).await?;
+    assert_eq!(error["error"]["code"], -32601);
+    let error =
+        call(json!({"jsonrpc": "2.0", "id": 5, "method": "select", "params": {"id": 5}})).await?;
+    assert_eq!(error["error"]["code"], -32602);
+
+    // nullのidはnotificationではない
+    let error = call(json!({"jsonrpc": "2.0", "id": null, "method": "foo"})).await?;
+    assert_eq!(error["id"], Value::Null);
+    assert_eq!(error["error"]["code"], -32601);
+
+    let id = items[0]["id"].clone();
+    let done =
+        call(json!({"jsonrpc": "2.0", "id": 6, "method": "select", "params": {"id": id}})).await?;
+    assert_eq!(done["id"], 6);
+    assert_eq!(done["result"], Value::Null);
+
+    launcher.await??;
+    assert_eq!(*selected.lock().unwrap(), Some(4));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rpc_live() -> Result<(), Box<dyn std::error::Error>> {
+    use ltrait::source::SourceEvent;
+    use ltrait::tokio_stream::wrappers::UnboundedReceiverStream;
+
+    let (front, back) = tokio::io::duplex(1024);
+    let (back_read, back_write) = tokio::io::split(back);
+    let (front_read, mut front_write) = tokio::io::split(front);
+    let mut responses = BufReader::new(front_read).lines();
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let launcher = Launcher::default()
+        .add_live_source(
+            Box::pin(UnboundedReceiverStream::new(rx)),
+            std::convert::identity,
+        )
+        .set_ui(RpcUI::new(back_read, back_write), |x: &i32| x.to_string());
+    let launcher = tokio::spawn(launcher.run());
+
+    let mut call = async |request: Value| -> Result<Value, Box<dyn std::error::Error>> {
+        front_write
+            .write_all(format!("{request}\n").as_bytes())
+            .await?;
+        let line = responses.next_line().await?.unwrap();
+        Ok(serde_json::from_str(&line)?)
+    };
+
+    tx.send(SourceEvent::Insert("a", 1))?;
+    tx.send(SourceEvent::Insert("b", 2))?;
+    let mut status =
+        call(json!({"jsonrpc": "2.0", "id": 1, "method": "input", "params": {"input": ""}}))
+            .await?;
+    while status["result"]["more"] == true {
+        status = call(json!({"jsonrpc": "2.0", "id": 2, "method": "poll"})).await?;
+    }
+    assert_eq!(status["result"]["total"], 2);
+    assert_eq!(status["result"]["live"], true);
+
+    // 全部のbatchが終わった後もpollで変更が届く
+    tx.send(SourceEvent::Update("a", 10))?;
+    tx.send(SourceEvent::Remove("b"))?;
+    let status = call(json!({"jsonrpc": "2.0", "id": 3, "method": "poll"})).await?;
+    assert_eq!(status["result"]["total"], 1);
+    let page = call(
+        json!({"jsonrpc": "2.0", "id": 4, "method": "page", "params": {"offset": 0, "count": 10}}),
+    )
+    .await?;
+    assert_eq!(page["result"]["items"][0]["context"], "10");
+
+    drop(tx);
+    let status = call(json!({"jsonrpc": "2.0", "id": 5, "method": "poll"})).await?;
+    assert_eq!(status["result"]["live"], false);
+
+    call(json!({"jsonrpc": "2.0", "id": 6, "method": "cancel"})).await?;
+    launcher.await??;
+
+    Ok(())
+}
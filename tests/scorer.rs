@@ -0,0 +1,44 @@
+use dummyui::DummyUI;
+use ltrait::scorer::ClosureScorer;
+use ltrait::{Launcher, source::from_iter};
+use std::convert::identity;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+mod dummyui;
+
+const COUNT: i32 = 100;
+
+#[tokio::test]
+async fn test_scorer() -> Result<(), Box<dyn std::error::Error>> {
+    let order = Arc::new(Mutex::new(vec![]));
+    let order_c = order.clone();
+    let launcher = Launcher::default()
+        .add_source(from_iter(0..COUNT), identity)
+        .batch_size(7)
+        .add_scorer(
+            ClosureScorer::new(|&x: &i32, _| (x % 10) as f64),
+            10.0,
+            |&c: &i32| c,
+        )
+        .add_scorer(
+            ClosureScorer::new(|&x: &i32, _| x as f64),
+            0.01,
+            |&c: &i32| c,
+        )
+        .set_ui(
+            DummyUI::new(|&x: &i32| {
+                (*order).lock().unwrap().push(x);
+            }),
+            |&c: &i32| c,
+        );
+
+    launcher.run().await?;
+
+    let mut expected: Vec<_> = (0..COUNT).collect();
+    expected.sort_by_key(|&x| std::cmp::Reverse((x % 10, x)));
+
+    assert_eq!(*(*order_c).lock().unwrap(), expected);
+
+    Ok(())
+}
@@ -1,5 +1,8 @@
 use dummyui::DummyUI;
-use ltrait::{Launcher, source::from_iter};
+use ltrait::color_eyre::eyre::Result as EyreResult;
+use ltrait::launcher::batcher::Batcher;
+use ltrait::ui::{Buffer, Selection};
+use ltrait::{Launcher, UI, source::from_iter};
 use std::convert::identity;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -27,3 +30,72 @@ async fn test_source() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_try_source() -> Result<(), Box<dyn std::error::Error>> {
+    use ltrait::color_eyre::eyre::eyre;
+
+    let launcher = Launcher::default()
+        .add_try_source(
+            from_iter(vec![Ok(1), Err(eyre!("failed to read")), Ok(2)]),
+            identity,
+        )
+        .set_ui(DummyUI::new(|_: &i32| {}), |&c: &i32| c);
+
+    let err = launcher.run().await.unwrap_err();
+    assert_eq!(err.to_string(), "failed to read");
+
+    Ok(())
+}
+
+/// Merges all the batches, collecting the errors instead of returning them
+struct TolerantUI(Arc<Mutex<(Vec<i32>, Vec<String>)>>);
+
+impl UI<i32> for TolerantUI {
+    type Context = i32;
+
+    async fn run(&self, mut batcher: Batcher<i32, i32>) -> EyreResult<Option<Selection<i32>>> {
+        let mut buf: Buffer<(i32, usize)> = Buffer::default();
+        let mut errors = vec![];
+
+        let mut more = true;
+        while more {
+            let from = batcher.prepare().await;
+            more = match batcher.merge(&mut buf, from) {
+                Ok(more) => more,
+                Err(e) => {
+                    errors.push(e.to_string());
+                    true
+                }
+            };
+        }
+
+        *self.0.lock().unwrap() = (buf.as_slice().iter().map(|(x, _)| *x).collect(), errors);
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn test_try_source_keeps_items() -> Result<(), Box<dyn std::error::Error>> {
+    use ltrait::color_eyre::eyre::eyre;
+
+    let result = Arc::new(Mutex::new((vec![], vec![])));
+    Launcher::default()
+        .add_source(from_iter(0..10), identity)
+        .add_try_source(
+            from_iter(vec![Ok(10), Err(eyre!("failed to read")), Ok(11)]),
+            identity,
+        )
+        .add_source(from_iter(20..30), identity)
+        .set_ui(TolerantUI(result.clone()), |&c: &i32| c)
+        .run()
+        .await?;
+
+    let (mut items, errors) = result.lock().unwrap().clone();
+    items.sort();
+    let expected: Vec<_> = (0..12).chain(20..30).collect();
+    assert_eq!(items, expected);
+    assert_eq!(errors, ["failed to read"]);
+
+    Ok(())
+}
@@ -0,0 +1,205 @@
+#![cfg(feature = "tui")]
+
+use ltrait::Launcher;
+use ltrait::action::ClosureAction;
+use ltrait::color_eyre::Result;
+use ltrait::filter::ClosureFilter;
+use ltrait::source::{Source, from_iter};
+use ltrait::ui::tui::Tui;
+
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_stream::StreamExt;
+
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn key(tx: &UnboundedSender<Event>, code: KeyCode) {
+    tx.send(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+        .unwrap();
+}
+
+fn screen(terminal: &Mutex<Terminal<TestBackend>>) -> Vec<String> {
+    let terminal = terminal.lock().unwrap();
+    let buffer = terminal.backend().buffer();
+    (0..buffer.area.height)
+        .map(|y| {
+            (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Waits until the screen is `expected`
+async fn wait_for(terminal: &Mutex<Terminal<TestBackend>>, expected: &[&str]) {
+    for _ in 0..500 {
+        if screen(terminal) == expected {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(screen(terminal), expected);
+}
+
+fn launcher(
+    tui: Tui<i32, TestBackend>,
+    items: Range<i32>,
+    selected: Arc<Mutex<Vec<String>>>,
+) -> Launcher<i32, Tui<i32, TestBackend>, i32> {
+    let selected_q = selected.clone();
+
+    Launcher::default()
+        .add_source(from_iter(items), std::convert::identity)
+        .add_raw_filter(ClosureFilter::new(|x: &i32, input: &str| {
+            x.to_string().contains(input)
+        }))
+        .add_action(
+            ClosureAction::new(move |x: &String| {
+                selected.lock().unwrap().push(x.clone());
+                Ok(())
+            }),
+            |x: &i32| x.to_string(),
+        )
+        .add_raw_fallback_action(ClosureAction::new(move |q: &String| {
+            selected_q.lock().unwrap().push(format!("query: {q}"));
+            Ok(())
+        }))
+        .set_ui(tui, |x: &i32| *x)
+}
+
+#[tokio::test]
+async fn test_tui() -> Result<()> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let (tui, terminal) = Tui::with_backend(TestBackend::new(10, 5), rx)?;
+    let selected = Arc::new(Mutex::new(vec![]));
+
+    let handle = tokio::spawn(launcher(tui, 0..100, selected.clone()).run());
+
+    key(&tx, KeyCode::Char('4'));
+    key(&tx, KeyCode::Char('x'));
+    key(&tx, KeyCode::Backspace);
+    wait_for(&terminal, &["  24", "  14", "> 4", "  19", "> 4"]).await;
+
+    // 下から並ぶので上で次の候補
+    key(&tx, KeyCode::Up);
+    key(&tx, KeyCode::Up);
+    key(&tx, KeyCode::Up);
+    wait_for(&terminal, &["> 34", "  24", "  14", "  19", "> 4"]).await;
+
+    key(&tx, KeyCode::Enter);
+    handle.await??;
+
+    assert_eq!(*selected.lock().unwrap(), ["34"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tui_query() -> Result<()> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let (tui, terminal) = Tui::with_backend(TestBackend::new(10, 4), rx)?;
+    let tui = tui.prompt("$ ");
+    let selected = Arc::new(Mutex::new(vec![]));
+
+    let handle = tokio::spawn(launcher(tui, 0..100, selected.clone()).run());
+
+    for c in "abc".chars() {
+        key(&tx, KeyCode::Char(c));
+    }
+    wait_for(&terminal, &["", "", "  0", "$ abc"]).await;
+    assert_eq!(
+        terminal.lock().unwrap().get_cursor_position()?,
+        (5, 3).into()
+    );
+
+    key(&tx, KeyCode::Enter);
+    handle.await??;
+
+    assert_eq!(*selected.lock().unwrap(), ["query: abc"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tui_cancel() -> Result<()> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let (tui, _terminal) = Tui::with_backend(TestBackend::new(10, 4), rx)?;
+    let selected = Arc::new(Mutex::new(vec![]));
+
+    let handle = tokio::spawn(launcher(tui, 0..100, selected.clone()).run());
+    key(&tx, KeyCode::Esc);
+    handle.await??;
+
+    assert!(selected.lock().unwrap().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tui_empty() -> Result<()> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let (tui, terminal) = Tui::with_backend(TestBackend::new(10, 4), rx)?;
+    let selected = Arc::new(Mutex::new(vec![]));
+
+    let handle = tokio::spawn(launcher(tui, 0..0, selected.clone()).run());
+    wait_for(&terminal, &["", "", "  0", ">"]).await;
+
+    // 何もないところでEnterしても空の入力は選ばない
+    key(&tx, KeyCode::Enter);
+    key(&tx, KeyCode::Char('a'));
+    wait_for(&terminal, &["", "", "  0", "> a"]).await;
+    assert!(!handle.is_finished());
+
+    key(&tx, KeyCode::Enter);
+    handle.await??;
+
+    assert_eq!(*selected.lock().unwrap(), ["query: a"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tui_slow() -> Result<()> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let (tui, terminal) = Tui::with_backend(TestBackend::new(10, 5), rx)?;
+    let selected = Arc::new(Mutex::new(vec![]));
+    let selected_a = selected.clone();
+
+    // 最初の3つの後は終わらない遅いsource
+    let slow = tokio_stream::iter(3..).then(|x| async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        x
+    });
+    let source: Source<i32> = Box::pin(from_iter(0..3).chain(slow));
+
+    let launcher = Launcher::default()
+        .batch_size(1)
+        .add_raw_source(source)
+        .add_action(
+            ClosureAction::new(move |x: &String| {
+                selected_a.lock().unwrap().push(x.clone());
+                Ok(())
+            }),
+            |x: &i32| x.to_string(),
+        )
+        .set_ui(tui, |x: &i32| *x);
+    let handle = tokio::spawn(launcher.run());
+
+    for _ in 0..500 {
+        if screen(&terminal)[..3] == ["  2", "  1", "> 0"] {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(screen(&terminal)[..3], ["  2", "  1", "> 0"]);
+
+    // 残りのbatchを待たずに表示中の行を選ぶ
+    key(&tx, KeyCode::Up);
+    key(&tx, KeyCode::Enter);
+    tokio::time::timeout(Duration::from_secs(5), handle).await???;
+
+    assert_eq!(*selected.lock().unwrap(), ["1"]);
+    Ok(())
+}